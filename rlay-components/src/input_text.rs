@@ -35,39 +35,42 @@ def_comp! {
         timer.set((timer.get() + 1) % 100);
 
         if is_focused {
-            if (ctx.utils.is_key_pressed)(RlayKeyboardKey::KEY_BACKSPACE) {
-                if !input_text.is_empty() {
-                    input_state.set(format!("{}", &input_text[..input_text.len() - 1]));
-                }
+            let mut text = input_text.clone();
+            if ctx.is_key_pressed_repeat(RlayKeyboardKey::KEY_BACKSPACE) {
+                text.pop();
             }
-            if let Some(chr) = ctx.get_input_state().keyboard.last_char_pressed {
+            let mut submitted = ctx.is_key_pressed(RlayKeyboardKey::KEY_ENTER);
+            for &chr in ctx.chars_pressed() {
                 match chr {
-                    '\n' | '\r' => {
-                        ctx.set_focused(None);
-                    }
+                    '\n' | '\r' => submitted = true,
                     '\x08' => {
-                        if !input_text.is_empty() {
-                            input_state.set(format!("{}", &input_text[..input_text.len() - 1]));
-                        }
-                    }
-                    _ => {
-                        input_state.set(format!("{}{}", input_text, chr));
+                        text.pop();
                     }
+                    _ => text.push(chr),
                 }
             }
+            if submitted {
+                ctx.set_focused(None);
+            }
+            if text != input_text {
+                input_state.set(text);
+            }
         }
 
         rlay!(ctx, view[id=&id](
             sizing = {Grow, Grow},
             align = { y = Center },
             padding = Padding::default().left(5),
+            focusable = true,
             background_color = if is_focused { LIGHTGRAY } else { WHITE },
             border = {
                 color = BLACK,
                 width = 1.0,
             }
         ) {
-            if ctx.state().is_clicked(&id) {
+            // Enter also clicks the focused input, which must not focus it again
+            // when Enter removed the focus
+            if !is_focused && ctx.state().is_clicked(&id) {
                 ctx.set_focused(Some(id.to_string()));
                 timer.set(0);
             }
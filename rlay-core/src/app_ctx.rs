@@ -1,46 +1,167 @@
-use std::{any::Any, collections::HashMap, sync::{Arc, Mutex}};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
 
 use crate::{
-    err::RlayError, mem::ArenaElement, AppState, Dimension2D, Done, Element, ElementLayout, ElementState, Initial, InputState, MinMax, Point2D, RlayKeyboardKey, Sizing, SizingAxis, TextConfig, TextDimensions
+    AppState, Dimension2D, Done, Element, ElementLayout, ElementState, Event, EventHandlers,
+    FontData, FontRegistry, Initial, InputState, MinMax, NavigationKeys, Point2D, RlayKeyboardKey,
+    Sizing, SizingAxis, TextConfig, TextDimensions, err::RlayError, layout::layout_text_lines,
+    mem::ArenaElement,
 };
 
 pub struct AppCtx {
     parent_stack: Vec<usize>,
     elements: ArenaElement,
     state: AppState,
+    fonts: FontRegistry,
+    handlers: EventHandlers,
+    /// The elements were moved out of the arena to build the layout
+    laid_out: bool,
     pub utils: AppCtxUtils,
 }
 
 pub struct AppCtxUtils {
-    pub measure_text: Box<dyn Fn(&str, &TextConfig) -> TextDimensions>,
+    /// Measures the text in the font, or in the default font of the backend
+    /// when it is `None`
+    pub measure_text: Box<dyn Fn(&str, &TextConfig, Option<&FontData>) -> TextDimensions>,
+    /// Still read by [`AppCtx::is_key_pressed`], for the backends which don't
+    /// fill [`crate::KeyboardInput::keys_pressed`] yet
+    #[deprecated(note = "the keys are read from the `InputState` of the backend, \
+                         build the utils with `AppCtxUtils::new`")]
     pub is_key_pressed: Box<dyn Fn(RlayKeyboardKey) -> bool>,
 }
 
+impl AppCtxUtils {
+    /// Utils measuring the texts with `measure_text`, the keys being read
+    /// from the input state only
+    pub fn new(
+        measure_text: impl Fn(&str, &TextConfig, Option<&FontData>) -> TextDimensions + 'static,
+    ) -> Self {
+        #[allow(deprecated)]
+        Self {
+            measure_text: Box::new(measure_text),
+            is_key_pressed: Box::new(|_| false),
+        }
+    }
+}
+
 impl AppCtx {
     pub fn new(fns: AppCtxUtils) -> Self {
         Self {
             parent_stack: vec![],
             elements: ArenaElement::default(),
             state: AppState::default(),
+            fonts: FontRegistry::default(),
+            handlers: EventHandlers::default(),
+            laid_out: false,
             utils: fns,
         }
     }
 
+    /// The id the next element opened without an id will get
     pub fn get_local_id(&self) -> String {
-        self.parent_stack
-            .iter()
-            .map(|p_idx| {
-                let nb_child = self.elements.get_nb_children(*p_idx).unwrap_or(0);
-                format!("{}-{}", p_idx, nb_child)
-            })
-            .reduce(|x, y| x + "_" + &y)
-            .unwrap_or_default()
+        self.next_element_id(None, None, None)
+    }
+
+    /// The id of the next element opened in the current parent.
+    ///
+    /// An element given an `id` keeps it, with the `index` added to it like
+    /// [`id_with_index`]. Otherwise, the id is a hash of the id of its parent
+    /// and of its `key` (or `index`), falling back to its position among its
+    /// siblings. The same tree therefore gets the same ids on every frame.
+    pub fn next_element_id(
+        &self,
+        id: Option<&str>,
+        index: Option<&str>,
+        key: Option<&str>,
+    ) -> String {
+        if let Some(id) = id {
+            return match index {
+                Some(index) => id_with_index(id, index),
+                None => id.to_string(),
+            };
+        }
+
+        let parent_idx = self.parent_stack.last().copied();
+        let parent_id = parent_idx
+            .and_then(|idx| self.elements.get_val(idx))
+            .map(|parent| parent.id().as_str())
+            .unwrap_or_default();
+
+        let hash = match key.or(index) {
+            Some(key) => hash_id(parent_id, b'k', key),
+            None => {
+                let sibling_idx = parent_idx
+                    .and_then(|idx| self.elements.get_nb_children(idx))
+                    .unwrap_or(0);
+                hash_id(parent_id, b'i', &sibling_idx.to_string())
+            }
+        };
+
+        format!("{:016x}", hash)
+    }
+
+    /// Registers the font file under `name`, so texts can use it with
+    /// `font_name` or the returned `font_id`
+    pub fn register_font_file(&mut self, name: &str, path: &str) -> u16 {
+        self.fonts.register_file(name, path)
+    }
+
+    /// Registers the content of a font file under `name`, so texts can use it
+    /// with `font_name` or the returned `font_id`
+    pub fn register_font_bytes(&mut self, name: &str, bytes: &[u8]) -> u16 {
+        self.fonts.register_bytes(name, bytes)
+    }
+
+    /// Uses the font file for the texts without a registered font, in place
+    /// of the default font of the backend
+    pub fn set_default_font_file(&mut self, path: &str) {
+        self.fonts.set_default_file(path);
+    }
+
+    pub fn fonts(&self) -> &FontRegistry {
+        &self.fonts
+    }
+
+    /// Measures the text in its font, falling back to the default font when
+    /// it is not registered
+    pub fn measure_text(&self, text: &str, config: &TextConfig) -> TextDimensions {
+        (self.utils.measure_text)(text, config, self.fonts.resolve(config))
     }
 
     pub fn get_input_state(&self) -> &InputState {
         self.state.input_state()
     }
 
+    pub fn is_key_down(&self, key: RlayKeyboardKey) -> bool {
+        self.get_input_state().keyboard.keys_down.contains(&key)
+    }
+
+    pub fn is_key_pressed(&self, key: RlayKeyboardKey) -> bool {
+        #[allow(deprecated)]
+        let is_key_pressed = &self.utils.is_key_pressed;
+        self.get_input_state().keyboard.keys_pressed.contains(&key) || is_key_pressed(key)
+    }
+
+    pub fn is_key_released(&self, key: RlayKeyboardKey) -> bool {
+        self.get_input_state().keyboard.keys_released.contains(&key)
+    }
+
+    /// Whether the key is pressed or repeated this frame, like a key typing
+    /// characters
+    pub fn is_key_pressed_repeat(&self, key: RlayKeyboardKey) -> bool {
+        let keyboard = &self.get_input_state().keyboard;
+        keyboard.keys_pressed.contains(&key) || keyboard.keys_repeated.contains(&key)
+    }
+
+    /// Every character typed this frame, in order
+    pub fn chars_pressed(&self) -> &[char] {
+        &self.get_input_state().keyboard.chars_pressed
+    }
+
     pub(crate) fn set_input_state(&mut self, input_state: InputState) {
         self.state.set_input_state(input_state);
     }
@@ -49,10 +170,54 @@ impl AppCtx {
         self.state.update_hovered_elements(element);
     }
 
+    pub(crate) fn update_scroll_offsets(&mut self, element: &ElementLayout<Done>) {
+        self.state.update_scroll_offsets(element);
+    }
+
+    /// Registers a handler called with the events aimed at the element or
+    /// bubbling up from its children. It is only kept for the current frame,
+    /// so it is registered again while building every frame.
+    pub fn on_event(
+        &mut self,
+        element_id: &str,
+        handler: impl FnMut(&mut Event, &mut AppState) + 'static,
+    ) {
+        self.handlers.register(element_id, Box::new(handler));
+    }
+
+    /// The events of the last frame, in the order they were dispatched
+    pub fn events(&self) -> &[Event] {
+        self.state.events()
+    }
+
+    pub(crate) fn dispatch_events(&mut self) {
+        let mut events = self.state.events().to_vec();
+        for event in &mut events {
+            let path = self.state.path(&event.target);
+            self.handlers.dispatch(event, &path, &mut self.state);
+        }
+    }
+
+    pub fn get_scroll_offset(&self, element_id: &str) -> Point2D {
+        self.state.scroll_offset(element_id)
+    }
+
+    pub fn set_scroll_offset(&mut self, element_id: &str, offset: Point2D) {
+        self.state.set_scroll_offset(element_id, offset);
+    }
+
     pub fn is_hovered(&self, element_id: &str) -> bool {
         self.state.is_hovered(element_id)
     }
 
+    pub fn pointer_over_ids(&self) -> &[String] {
+        self.state.pointer_over_ids()
+    }
+
+    pub fn pointer_target(&self) -> Option<&str> {
+        self.state.pointer_target()
+    }
+
     pub fn set_flag(&mut self, element_id: &str, flag: impl ToString, value: bool) -> bool {
         self.state.set_flag(element_id, flag, value)
     }
@@ -94,6 +259,10 @@ impl AppCtx {
         self.state.set_focused(element_id);
     }
 
+    pub fn set_navigation_keys(&mut self, navigation_keys: Option<NavigationKeys>) {
+        self.state.set_navigation_keys(navigation_keys);
+    }
+
     pub fn state(&self) -> &AppState {
         &self.state
     }
@@ -109,15 +278,29 @@ impl AppCtx {
     pub(crate) fn clear(&mut self) {
         self.parent_stack.clear();
         self.elements.clear();
+        self.handlers.clear();
+        self.laid_out = false;
     }
 
+    /// The element is only there while the view is built: building the layout
+    /// moves the elements out, and [`RlayError::ElementLaidOut`] is returned
+    /// after that. Use [`ElementLayout::get_element_with_id`] on the layout
+    /// instead.
     pub fn get_element_with_id(&self, id: &str) -> Result<&Element, RlayError> {
+        if self.laid_out {
+            return Err(RlayError::ElementLaidOut);
+        }
         self.elements
             .get_element_with_id(id)
             .ok_or(RlayError::ElementNotFound)
     }
 
+    /// Like [`AppCtx::get_element_with_id`], only there while the view is
+    /// built
     pub fn current_element(&self) -> Result<&Element, RlayError> {
+        if self.laid_out {
+            return Err(RlayError::ElementLaidOut);
+        }
         let current_idx = self
             .parent_stack
             .first()
@@ -168,23 +351,51 @@ impl AppCtx {
     }
 }
 
+/// The id of one of many elements sharing the same id, e.g. the items of a list
+pub fn id_with_index(id: &str, index: impl Display) -> String {
+    format!("{}[{}]", id, index)
+}
+
+/// FNV-1a hash of an element id, from the id of its parent and the part that
+/// tells it apart from its siblings
+fn hash_id(parent_id: &str, kind: u8, discriminant: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    parent_id
+        .bytes()
+        .chain([0, kind])
+        .chain(discriminant.bytes())
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(PRIME)
+        })
+}
+
 impl TryFrom<&mut AppCtx> for ElementLayout<Initial> {
     type Error = RlayError;
 
     fn try_from(value: &mut AppCtx) -> Result<Self, Self::Error> {
         let root = *value.parent_stack.first().ok_or(RlayError::NoRoot)?;
-        let root_value = value
-            .elements
-            .get_val(root)
-            .ok_or(RlayError::ElementNotFound)?;
+        value.laid_out = true;
 
-        unpack_node(value, root_value)
+        let (utils, fonts) = (&value.utils, &value.fonts);
+        let measure_text = |text: &str, config: &TextConfig| {
+            (utils.measure_text)(text, config, fonts.resolve(config))
+        };
+        unpack_node(&mut value.elements, &measure_text, root)
     }
 }
 
-fn unpack_node(ctx: &AppCtx, node: &Element) -> Result<ElementLayout<Initial>, RlayError> {
-    let arena = &ctx.elements;
-    match node {
+/// Builds the layout of the node at `idx` and of its children. The elements are
+/// moved out of the arena, which is left empty until it is cleared.
+fn unpack_node(
+    arena: &mut ArenaElement,
+    measure_text: &dyn Fn(&str, &TextConfig) -> TextDimensions,
+    idx: usize,
+) -> Result<ElementLayout<Initial>, RlayError> {
+    let node = arena.take_val(idx).ok_or(RlayError::ElementNotFound)?;
+
+    match &node {
         Element::Container(container) => {
             let config = container.config();
             let Sizing { width, height } = config.sizing;
@@ -193,45 +404,84 @@ fn unpack_node(ctx: &AppCtx, node: &Element) -> Result<ElementLayout<Initial>, R
                 SizingAxis::Fixed(w) => w,
                 SizingAxis::Fit(MinMax { min, .. }) => min.unwrap_or(0.0),
                 SizingAxis::Grow(MinMax { min, .. }) => min.unwrap_or(0.0),
-                SizingAxis::Percent(_) => 0.0,
+                // Resolved against the parent in the grow/shrink steps
+                SizingAxis::Percent(..) => 0.0,
             };
             let height = match height {
                 SizingAxis::Fixed(h) => h,
                 SizingAxis::Fit(MinMax { min, .. }) => min.unwrap_or(0.0),
                 SizingAxis::Grow(MinMax { min, .. }) => min.unwrap_or(0.0),
-                SizingAxis::Percent(_) => 0.0,
+                SizingAxis::Percent(..) => 0.0,
             };
 
-            let idx = arena.find(&node).ok_or(RlayError::ElementNotFound)?;
-            let children = arena.get_children_val(idx);
+            let nb_children = arena.get_nb_children(idx).unwrap_or(0);
+            let children = (0..nb_children)
+                .map(|child_nb| {
+                    let child_idx =
+                        arena.get_children(idx).ok_or(RlayError::ElementNotFound)?[child_nb];
+                    unpack_node(arena, measure_text, child_idx)
+                })
+                .collect::<Result<Box<[_]>, _>>()?;
 
             Ok(ElementLayout::new(
                 Point2D::default(),
                 Dimension2D::new(width, height),
-                node.clone(),
-                children
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|child| unpack_node(ctx, child))
-                    .collect::<Result<Box<[_]>, _>>()?,
+                node,
+                children,
             ))
         }
 
         Element::Text(text) => {
-            let TextDimensions {
-                width,
-                height,
-                offset_y,
-            } = (ctx.utils.measure_text)(text.data(), text.config());
+            let TextDimensions { offset_y, .. } = measure_text(text.data(), text.config());
+            // The words are wrapped once the width of the parent is known
+            let (lines, dimensions) =
+                layout_text_lines(measure_text, text.data(), text.config(), f32::INFINITY);
+
+            let Element::Text(mut text) = node else {
+                unreachable!()
+            };
+            text.lines = lines;
 
             Ok(ElementLayout::new(
                 Point2D::new(0.0, offset_y),
-                Dimension2D::new(width, height),
-                node.clone(),
+                dimensions,
+                Element::Text(text),
                 Box::new([]),
             ))
         }
 
-        Element::Image(..) => todo!(),
+        Element::Image(image) => {
+            let config = image.config();
+            let Sizing { width, height } = config.sizing;
+
+            // A fit axis follows the source image, unless the other axis is
+            // fixed, in which case it keeps the aspect ratio of the image
+            let image_width = match width {
+                SizingAxis::Fixed(w) => w,
+                SizingAxis::Fit(min_max) => match height {
+                    SizingAxis::Fixed(h) => min_max.clamp(config.width_for_height(h)),
+                    _ => min_max.clamp(config.src_dimensions.width),
+                },
+                SizingAxis::Grow(MinMax { min, .. }) => min.unwrap_or(0.0),
+                // Resolved against the parent in the grow/shrink steps
+                SizingAxis::Percent(..) => 0.0,
+            };
+            let image_height = match height {
+                SizingAxis::Fixed(h) => h,
+                SizingAxis::Fit(min_max) => match width {
+                    SizingAxis::Fixed(w) => min_max.clamp(config.height_for_width(w)),
+                    _ => min_max.clamp(config.src_dimensions.height),
+                },
+                SizingAxis::Grow(MinMax { min, .. }) => min.unwrap_or(0.0),
+                SizingAxis::Percent(..) => 0.0,
+            };
+
+            Ok(ElementLayout::new(
+                Point2D::default(),
+                Dimension2D::new(image_width, image_height),
+                node,
+                Box::new([]),
+            ))
+        }
     }
 }
@@ -22,23 +22,41 @@ pub enum SizingAxis {
     Fit(MinMax),
     Fixed(f32),
     Grow(MinMax),
-    /// A value between 0 and 1
-    Percent(f32),
+    /// A value between 0 and 1, the part of the parent taken, bounded by the
+    /// min and max
+    Percent(f32, MinMax),
 }
 
 impl SizingAxis {
     pub fn get_max(&self) -> f32 {
         match self {
-            SizingAxis::Fit(min_max) | Self::Grow(min_max) => min_max.get_max(),
+            SizingAxis::Fit(min_max) | Self::Grow(min_max) | Self::Percent(_, min_max) => {
+                min_max.get_max()
+            }
             SizingAxis::Fixed(val) => *val,
-            SizingAxis::Percent(_) => todo!(),
         }
     }
+
     pub fn get_min(&self) -> f32 {
         match self {
-            SizingAxis::Fit(min_max) | Self::Grow(min_max) => min_max.get_min(),
+            SizingAxis::Fit(min_max) | Self::Grow(min_max) | Self::Percent(_, min_max) => {
+                min_max.get_min()
+            }
             SizingAxis::Fixed(val) => *val,
-            SizingAxis::Percent(_) => todo!(),
+        }
+    }
+
+    /// Clamps the value to be >= min and <= max of the sizing axis
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.get_min(), self.get_max())
+    }
+
+    /// Resolves a percent sizing against the size available in the parent,
+    /// within its min and max. Returns `None` for the other sizing types.
+    pub fn resolve_percent(&self, available: f32) -> Option<f32> {
+        match self {
+            SizingAxis::Percent(val, min_max) => Some(min_max.clamp(available.max(0.0) * val)),
+            _ => None,
         }
     }
 }
@@ -161,6 +179,19 @@ impl Padding {
     }
 }
 
+impl Add for Padding {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(
+            self.left + rhs.left,
+            self.top + rhs.top,
+            self.right + rhs.right,
+            self.bottom + rhs.bottom,
+        )
+    }
+}
+
 impl From<[i32; 4]> for Padding {
     fn from(value: [i32; 4]) -> Self {
         Self::new(value[0], value[1], value[2], value[3])
@@ -259,6 +290,124 @@ pub mod colors {
     pub const MAGENTA: Color = Color::new_const(1.00, 0.00, 1.00, 1.00);
 }
 
+/// What fills the background of an element
+#[derive(Debug, Clone, PartialEq, From)]
+pub enum Background {
+    Color(Color),
+    Gradient(Gradient),
+}
+
+impl From<Color> for Option<Background> {
+    fn from(value: Color) -> Self {
+        Some(value.into())
+    }
+}
+
+impl From<Gradient> for Option<Background> {
+    fn from(value: Gradient) -> Self {
+        Some(value.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    /// Where the color is on the gradient, from 0.0 at its start to 1.0 at
+    /// its end
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl From<(f32, Color)> for ColorStop {
+    fn from((offset, color): (f32, Color)) -> Self {
+        Self { offset, color }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Gradient {
+    /// The colors change along a line going through the center of the element,
+    /// long enough for its corners to have the colors of the ends
+    Linear {
+        /// The direction of the line in radians. `0.0` goes from the left to
+        /// the right, and a quarter turn from the top to the bottom.
+        angle: f32,
+        /// The colors of the gradient, ordered by offset
+        stops: Vec<ColorStop>,
+    },
+    /// The colors change going away from the center
+    Radial {
+        /// The center relative to the size of the element, `(0.5, 0.5)` being
+        /// the center of the element
+        center: Point2D,
+        /// The distance of the end of the gradient from its center, or `None`
+        /// for it to reach the farthest corner
+        radius: Option<f32>,
+        /// The colors of the gradient, ordered by offset
+        stops: Vec<ColorStop>,
+    },
+}
+
+/// Spreads the colors evenly from `0.0` to `1.0`
+fn even_stops(colors: impl IntoIterator<Item = Color>) -> Vec<ColorStop> {
+    let colors: Vec<Color> = colors.into_iter().collect();
+    let last = colors.len().saturating_sub(1).max(1) as f32;
+    colors
+        .into_iter()
+        .enumerate()
+        .map(|(i, color)| ColorStop::from((i as f32 / last, color)))
+        .collect()
+}
+
+impl Gradient {
+    pub fn linear(angle: f32, colors: impl IntoIterator<Item = Color>) -> Self {
+        Self::Linear {
+            angle,
+            stops: even_stops(colors),
+        }
+    }
+
+    /// A radial gradient from the center of the element to its corners
+    pub fn radial(colors: impl IntoIterator<Item = Color>) -> Self {
+        Self::Radial {
+            center: Point2D::scalar(0.5),
+            radius: None,
+            stops: even_stops(colors),
+        }
+    }
+
+    pub fn stops(&self) -> &[ColorStop] {
+        match self {
+            Self::Linear { stops, .. } | Self::Radial { stops, .. } => stops,
+        }
+    }
+
+    /// The color at the offset, mixing the colors of the stops around it
+    pub fn color_at(&self, offset: f32) -> Color {
+        let stops = self.stops();
+        let Some(first) = stops.first() else {
+            return colors::BLANK;
+        };
+        if offset <= first.offset {
+            return first.color;
+        }
+
+        for pair in stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if offset <= to.offset {
+                let t = (offset - from.offset) / (to.offset - from.offset);
+                let mix = |from: f32, to: f32| from + (to - from) * t;
+                return Color::new_const(
+                    mix(from.color.r, to.color.r),
+                    mix(from.color.g, to.color.g),
+                    mix(from.color.b, to.color.b),
+                    mix(from.color.a, to.color.a),
+                );
+            }
+        }
+        stops[stops.len() - 1].color
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum LayoutDirection {
     #[default]
@@ -435,6 +584,17 @@ impl CorderRadius {
         )
     }
 
+    /// The same radii, made at most `max`
+    pub fn capped(self, max: f32) -> Self {
+        let (top_left, top_right, bottom_left, bottom_right) = self.to_tuple_capped(max);
+        Self::new(
+            Some(top_left),
+            Some(top_right),
+            Some(bottom_left),
+            Some(bottom_right),
+        )
+    }
+
     pub fn to_corner_layout(self) -> (Point2D, Dimension2D) {
         (
             Point2D::new(
@@ -524,27 +684,69 @@ pub enum FloatingAttachPointType {
     RightBottom,
 }
 
+impl FloatingAttachPointType {
+    /// The offset of the attach point from the top left corner of a box of
+    /// the given dimensions
+    pub fn offset_in(&self, dimensions: Dimension2D) -> Point2D {
+        let Dimension2D { width, height } = dimensions;
+        match self {
+            Self::LeftTop => Point2D::new(0.0, 0.0),
+            Self::LeftCenter => Point2D::new(0.0, height / 2.0),
+            Self::LeftBottom => Point2D::new(0.0, height),
+            Self::CenterTop => Point2D::new(width / 2.0, 0.0),
+            Self::CenterCenter => Point2D::new(width / 2.0, height / 2.0),
+            Self::CenterBottom => Point2D::new(width / 2.0, height),
+            Self::RightTop => Point2D::new(width, 0.0),
+            Self::RightCenter => Point2D::new(width, height / 2.0),
+            Self::RightBottom => Point2D::new(width, height),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct FloatingAttachPoint {
-    element: FloatingAttachPointType,
-    parent: FloatingAttachPointType,
+    /// The point of the floating element
+    pub element: FloatingAttachPointType,
+    /// The point of the element it is attached to
+    pub parent: FloatingAttachPointType,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+impl FloatingAttachPoint {
+    pub fn new(element: FloatingAttachPointType, parent: FloatingAttachPointType) -> Self {
+        Self { element, parent }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum FloatingAttachTo {
     #[default]
     Parent,
-    ElementWithId,
+    ElementWithId(String),
     Root,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum PointerCaptureMode {
+    /// The element catches the clicks, and when it is floating, the elements
+    /// under it are not hovered
     #[default]
     Capture,
+    /// The element and its children are hovered, but let the clicks through
+    /// to the elements under them, e.g. for decorative overlays
     Passthrough,
 }
 
+/// What the arrow keys do at the edges of a group of focusable elements, with
+/// [`crate::NavigationKeys`]. The focus only moves between the elements of the
+/// group, nested groups included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusGroup {
+    /// The focus stays on the last element in the direction
+    Trap,
+    /// The focus goes around to the element on the other side of the group
+    Wrap,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum BorderMode {
     #[default]
@@ -560,12 +762,93 @@ pub struct BorderConfig {
     pub mode: BorderMode,
 }
 
+impl BorderConfig {
+    /// The part of the border drawn inside of the element, which its children
+    /// leave free. It is rounded up to whole pixels, like the padding.
+    pub fn inner_width(&self) -> Padding {
+        let inside = match self.mode {
+            BorderMode::Outset => 0.0,
+            BorderMode::Midset => 0.5,
+            BorderMode::Inset => 1.0,
+        };
+        let (left, top, right, bottom) = self.width.to_tuple();
+        let side = |width: f32| (width * inside).ceil() as i32;
+
+        Padding::new(side(left), side(top), side(right), side(bottom))
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ShadowConfig {
+    /// Moves the shadow away from the element
+    pub offset: Point2D,
+    /// The distance over which the edges of the shadow fade out
+    pub blur_radius: f32,
+    /// Grows the shadow on every side, or shrinks it when negative
+    pub spread: f32,
+    pub color: Color,
+    /// Casts the shadow inside of the element, as if it was hollow
+    pub inset: bool,
+}
+
+/// Moves, rotates and scales how the element and its children are drawn,
+/// without changing their layout, like the CSS transforms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Point2D,
+    /// Clockwise, in radians
+    pub angle: f32,
+    pub scale: Point2D,
+    /// The point the element rotates and scales around, relative to its size.
+    /// `(0.5, 0.5)`, the center of the element, by default.
+    pub origin: Point2D,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Point2D::default(),
+            angle: 0.0,
+            scale: Point2D::scalar(1.0),
+            origin: Point2D::scalar(0.5),
+        }
+    }
+}
+
+impl Transform {
+    /// Where the origin is, for an element at `position`
+    pub fn pivot(&self, position: Point2D, dimensions: Dimension2D) -> Point2D {
+        position + Point2D::from(dimensions) * self.origin
+    }
+
+    /// Where the point is drawn, when it is in an element whose origin is at
+    /// `pivot`
+    pub fn apply(&self, point: Point2D, pivot: Point2D) -> Point2D {
+        let scaled = (point - pivot) * self.scale;
+        let (sin, cos) = self.angle.sin_cos();
+        let rotated = Point2D::new(
+            scaled.x * cos - scaled.y * sin,
+            scaled.x * sin + scaled.y * cos,
+        );
+        pivot + self.translation + rotated
+    }
+
+    /// The point of the layout drawn at `point`, undoing [`Self::apply`]
+    pub fn invert(&self, point: Point2D, pivot: Point2D) -> Point2D {
+        let moved = point - pivot - self.translation;
+        let (sin, cos) = self.angle.sin_cos();
+        let rotated = Point2D::new(moved.x * cos + moved.y * sin, moved.y * cos - moved.x * sin);
+        pivot + rotated / self.scale
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct FloatingConfig {
     pub offset: Point2D,
     pub expand: Dimension2D,
     pub z_index: u16,
     pub attach_point: FloatingAttachPoint,
+    pub attach_to: FloatingAttachTo,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -574,6 +857,73 @@ pub struct ScrollConfig {
     pub vertical: bool,
 }
 
+impl ScrollConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.horizontal || self.vertical
+    }
+
+    /// Keeps the value only on the axes that can be scrolled
+    pub fn restrict(&self, value: Point2D) -> Point2D {
+        Point2D::new(
+            if self.horizontal { value.x } else { 0.0 },
+            if self.vertical { value.y } else { 0.0 },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridTrack {
+    /// As big as the biggest child in the track
+    Fit(MinMax),
+    Fixed(f32),
+    /// A fraction of the space left by the other tracks, shared with the
+    /// other growing tracks in proportion to their value
+    Grow(f32),
+    /// A value between 0 and 1 of the content box of the grid
+    Percent(f32),
+}
+
+impl Default for GridTrack {
+    fn default() -> Self {
+        Self::Fit(MinMax::default())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GridConfig {
+    pub columns: Vec<GridTrack>,
+    /// Rows past the ones defined here are added as needed, they fit their
+    /// children
+    pub rows: Vec<GridTrack>,
+    pub column_gap: i32,
+    pub row_gap: i32,
+}
+
+/// The number of tracks a child of a grid takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridSpan {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl GridSpan {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Self { columns, rows }
+    }
+}
+
+impl Default for GridSpan {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+impl From<(usize, usize)> for GridSpan {
+    fn from(value: (usize, usize)) -> Self {
+        Self::new(value.0, value.1)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Alignment {
     #[default]
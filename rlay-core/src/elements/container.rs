@@ -1,59 +1,105 @@
-use rand::RngCore;
-
 use super::{
-    Alignment, BorderConfig, Color, Config, CorderRadius, FloatingConfig, LayoutAlignment,
-    LayoutDirection, Padding, PointerCaptureMode, ScrollConfig, Sizing,
+    Alignment, Background, BorderConfig, Config, CorderRadius, FloatingConfig, FocusGroup,
+    GridConfig, GridSpan, LayoutAlignment, LayoutDirection, Padding, PointerCaptureMode,
+    ScrollConfig, ShadowConfig, Sizing, Transform,
 };
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct ContainerConfig {
     pub sizing: Sizing,
-    pub background_color: Option<Color>,
+    /// Also set by `background_color` or `bg` in the macros
+    pub background: Option<Background>,
     pub padding: Padding,
     pub layout_direction: LayoutDirection,
     pub child_gap: i32,
     pub align: LayoutAlignment,
+    /// Children overflowing the layout direction go on a new line
+    pub wrap: bool,
+    /// The gap between the lines of a wrapping container
+    pub line_gap: i32,
+    /// Lays the children out in the cells of a grid instead of the layout direction
+    pub grid: Option<GridConfig>,
+    /// The cells taken by the element when its parent is a grid
+    pub grid_span: GridSpan,
 
     pub border: Option<BorderConfig>,
     pub corner_radius: Option<CorderRadius>,
+    /// Drawn under the element, or inside of it when it is inset
+    pub shadow: Option<ShadowConfig>,
+    /// Multiplies the alpha of everything drawn for the element and its
+    /// children, from 0.0 to 1.0
+    pub opacity: Option<f32>,
+    /// Draws the element and its children on a layer of their own, which is
+    /// then drawn with the opacity, so the children don't show through each
+    /// other where they overlap
+    pub layer: bool,
+    /// Changes how the element is drawn and where it reacts to the cursor,
+    /// but not its layout
+    pub transform: Option<Transform>,
     pub floating: Option<FloatingConfig>,
     pub scroll: ScrollConfig,
     pub pointer_capture: PointerCaptureMode,
     pub focusable: bool,
+    /// Where the element is in the order of Tab when it is focusable. The
+    /// elements with a tab index come first, from the lowest one, then the
+    /// others in the order of the tree.
+    pub tab_index: Option<u16>,
+    /// Keeps the focus moved by the arrow keys in the element
+    pub focus_group: Option<FocusGroup>,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct PartialContainerConfig {
     pub sizing: Option<Sizing>,
-    pub background_color: Option<Color>,
+    pub background: Option<Background>,
     pub padding: Option<Padding>,
     pub layout_direction: Option<LayoutDirection>,
     pub child_gap: Option<i32>,
     pub align: Option<LayoutAlignment>,
+    pub wrap: Option<bool>,
+    pub line_gap: Option<i32>,
+    pub grid: Option<GridConfig>,
+    pub grid_span: Option<GridSpan>,
 
     pub border: Option<BorderConfig>,
     pub corner_radius: Option<CorderRadius>,
+    pub shadow: Option<ShadowConfig>,
+    pub opacity: Option<f32>,
+    pub layer: Option<bool>,
+    pub transform: Option<Transform>,
     pub floating: Option<FloatingConfig>,
     pub scroll: Option<ScrollConfig>,
     pub pointer_capture: Option<PointerCaptureMode>,
     pub focusable: Option<bool>,
+    pub tab_index: Option<u16>,
+    pub focus_group: Option<FocusGroup>,
 }
 
 impl From<PartialContainerConfig> for ContainerConfig {
     fn from(value: PartialContainerConfig) -> Self {
         Self {
             sizing: value.sizing.unwrap_or_default(),
-            background_color: value.background_color,
+            background: value.background,
             padding: value.padding.unwrap_or_default(),
             layout_direction: value.layout_direction.unwrap_or_default(),
             child_gap: value.child_gap.unwrap_or_default(),
             align: value.align.unwrap_or_default(),
+            wrap: value.wrap.unwrap_or_default(),
+            line_gap: value.line_gap.unwrap_or_default(),
+            grid: value.grid,
+            grid_span: value.grid_span.unwrap_or_default(),
             border: value.border,
             corner_radius: value.corner_radius,
+            shadow: value.shadow,
+            opacity: value.opacity,
+            layer: value.layer.unwrap_or_default(),
+            transform: value.transform,
             floating: value.floating,
             scroll: value.scroll.unwrap_or_default(),
             pointer_capture: value.pointer_capture.unwrap_or_default(),
             focusable: value.focusable.unwrap_or_default(),
+            tab_index: value.tab_index,
+            focus_group: value.focus_group,
         }
     }
 }
@@ -62,17 +108,27 @@ impl From<ContainerConfig> for PartialContainerConfig {
     fn from(value: ContainerConfig) -> Self {
         Self {
             sizing: Some(value.sizing),
-            background_color: value.background_color,
+            background: value.background,
             padding: Some(value.padding),
             layout_direction: Some(value.layout_direction),
             child_gap: Some(value.child_gap),
             align: Some(value.align),
+            wrap: Some(value.wrap),
+            line_gap: Some(value.line_gap),
+            grid: value.grid,
+            grid_span: Some(value.grid_span),
             border: value.border,
             corner_radius: value.corner_radius,
+            shadow: value.shadow,
+            opacity: value.opacity,
+            layer: Some(value.layer),
+            transform: value.transform,
             floating: value.floating,
             scroll: Some(value.scroll),
             pointer_capture: Some(value.pointer_capture),
             focusable: Some(value.focusable),
+            tab_index: value.tab_index,
+            focus_group: value.focus_group,
         }
     }
 }
@@ -90,17 +146,27 @@ impl Config for PartialContainerConfig {
         let other = other.into();
         Self {
             sizing: other.sizing.or(self.sizing),
-            background_color: other.background_color.or(self.background_color),
+            background: other.background.or_else(|| self.background.clone()),
             padding: other.padding.or(self.padding),
             layout_direction: other.layout_direction.or(self.layout_direction),
             child_gap: other.child_gap.or(self.child_gap),
             align: other.align.or(self.align),
+            wrap: other.wrap.or(self.wrap),
+            line_gap: other.line_gap.or(self.line_gap),
+            grid: other.grid.or_else(|| self.grid.clone()),
+            grid_span: other.grid_span.or(self.grid_span),
             border: other.border.or(self.border),
             corner_radius: other.corner_radius.or(self.corner_radius),
-            floating: other.floating.or(self.floating),
+            shadow: other.shadow.or(self.shadow),
+            opacity: other.opacity.or(self.opacity),
+            layer: other.layer.or(self.layer),
+            transform: other.transform.or(self.transform),
+            floating: other.floating.or_else(|| self.floating.clone()),
             scroll: other.scroll.or(self.scroll),
             pointer_capture: other.pointer_capture.or(self.pointer_capture),
             focusable: other.focusable.or(self.focusable),
+            tab_index: other.tab_index.or(self.tab_index),
+            focus_group: other.focus_group.or(self.focus_group),
         }
     }
 }
@@ -112,48 +178,69 @@ impl Config for ContainerConfig {
         let other = other.into();
         Self {
             sizing: other.sizing.unwrap_or(self.sizing),
-            background_color: other.background_color.or(self.background_color),
+            background: other.background.or_else(|| self.background.clone()),
             padding: other.padding.unwrap_or(self.padding),
             layout_direction: other.layout_direction.unwrap_or(self.layout_direction),
             child_gap: other.child_gap.unwrap_or(self.child_gap),
             align: other.align.unwrap_or(self.align),
+            wrap: other.wrap.unwrap_or(self.wrap),
+            line_gap: other.line_gap.unwrap_or(self.line_gap),
+            grid: other.grid.or_else(|| self.grid.clone()),
+            grid_span: other.grid_span.unwrap_or(self.grid_span),
             border: other.border.or(self.border),
             corner_radius: other.corner_radius.or(self.corner_radius),
-            floating: other.floating.or(self.floating),
+            shadow: other.shadow.or(self.shadow),
+            opacity: other.opacity.or(self.opacity),
+            layer: other.layer.unwrap_or(self.layer),
+            transform: other.transform.or(self.transform),
+            floating: other.floating.or_else(|| self.floating.clone()),
             scroll: other.scroll.unwrap_or(self.scroll),
             pointer_capture: other.pointer_capture.unwrap_or(self.pointer_capture),
             focusable: other.focusable.unwrap_or(self.focusable),
+            tab_index: other.tab_index.or(self.tab_index),
+            focus_group: other.focus_group.or(self.focus_group),
         }
     }
 }
 
 impl ContainerConfig {
+    /// The space between the edges of the container and its children: its
+    /// padding and the part of its border inside of it
+    pub fn content_padding(&self) -> Padding {
+        match &self.border {
+            Some(border) => self.padding + border.inner_width(),
+            None => self.padding,
+        }
+    }
+
     pub fn padding_in_axis(&self) -> i32 {
+        let padding = self.content_padding();
         match self.layout_direction {
             LayoutDirection::LeftToRight => match self.align.x {
-                Alignment::Start => self.padding.left,
-                Alignment::End | Alignment::EndReverse => self.padding.right,
-                Alignment::Center => self.padding.left,
+                Alignment::Start => padding.left,
+                Alignment::End | Alignment::EndReverse => padding.right,
+                Alignment::Center => padding.left,
             },
             LayoutDirection::TopToBottom => match self.align.y {
-                Alignment::Start => self.padding.top,
-                Alignment::End | Alignment::EndReverse => self.padding.bottom,
-                Alignment::Center => self.padding.top,
+                Alignment::Start => padding.top,
+                Alignment::End | Alignment::EndReverse => padding.bottom,
+                Alignment::Center => padding.top,
             },
         }
     }
 
     pub fn padding_in_other_axis(&self) -> i32 {
+        let padding = self.content_padding();
         match self.layout_direction {
             LayoutDirection::TopToBottom => match self.align.x {
-                Alignment::Start => self.padding.left,
-                Alignment::End | Alignment::EndReverse => self.padding.right,
-                Alignment::Center => self.padding.left,
+                Alignment::Start => padding.left,
+                Alignment::End | Alignment::EndReverse => padding.right,
+                Alignment::Center => padding.left,
             },
             LayoutDirection::LeftToRight => match self.align.y {
-                Alignment::Start => self.padding.top,
-                Alignment::End | Alignment::EndReverse => self.padding.bottom,
-                Alignment::Center => self.padding.top,
+                Alignment::Start => padding.top,
+                Alignment::End | Alignment::EndReverse => padding.bottom,
+                Alignment::Center => padding.top,
             },
         }
     }
@@ -166,11 +253,8 @@ pub struct ContainerElement {
 }
 
 impl ContainerElement {
-    pub fn new(config: ContainerConfig, id: Option<String>) -> Self {
-        Self {
-            config,
-            id: id.unwrap_or_else(|| rand::rng().next_u64().to_string()),
-        }
+    pub fn new(config: ContainerConfig, id: String) -> Self {
+        Self { config, id }
     }
 
     pub fn config(&self) -> &ContainerConfig {
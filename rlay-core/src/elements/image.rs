@@ -1,12 +1,39 @@
-use rand::RngCore;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
 
-use crate::Dimension2D;
+use crate::{Dimension2D, Sizing};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct ImageConfig {
+    pub sizing: Sizing,
     pub src_dimensions: Dimension2D,
 }
 
+impl ImageConfig {
+    /// The ratio width / height of the source image, if it has a valid size
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        let Dimension2D { width, height } = self.src_dimensions;
+        (width > 0.0 && height > 0.0).then_some(width / height)
+    }
+
+    /// The width of the image when drawn with `height`, keeping the aspect ratio
+    pub fn width_for_height(&self, height: f32) -> f32 {
+        self.aspect_ratio()
+            .map(|ratio| height * ratio)
+            .unwrap_or(self.src_dimensions.width)
+    }
+
+    /// The height of the image when drawn with `width`, keeping the aspect ratio
+    pub fn height_for_width(&self, width: f32) -> f32 {
+        self.aspect_ratio()
+            .map(|ratio| width / ratio)
+            .unwrap_or(self.src_dimensions.height)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImageElement {
     pub id: String,
@@ -14,25 +41,81 @@ pub struct ImageElement {
     pub config: ImageConfig,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ImageData {
-    File { path: String },
-    Bytes { file_type: String, bytes: Box<[u8]> },
+    File {
+        path: String,
+    },
+    Bytes {
+        file_type: String,
+        bytes: ImageBytes,
+    },
 }
 
-impl ImageElement {
-    pub fn new(config: ImageConfig, data: ImageData, id: Option<String>) -> Self {
+/// The content of an image file. The images are the keys of the texture
+/// caches, so the bytes are hashed once, when they are created, and not on
+/// every lookup.
+#[derive(Debug, Clone)]
+pub struct ImageBytes {
+    bytes: Arc<[u8]>,
+    hash: u64,
+}
+
+impl ImageBytes {
+    pub fn new(bytes: Arc<[u8]>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
         Self {
-            config,
-            data,
-            id: id.unwrap_or_else(|| rand::rng().next_u64().to_string()),
+            hash: hasher.finish(),
+            bytes,
         }
     }
+}
+
+impl Deref for ImageBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.bytes
+    }
+}
+
+impl PartialEq for ImageBytes {
+    /// The bytes are only compared when they are not shared and have the same
+    /// hash
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.bytes, &other.bytes)
+            || (self.hash == other.hash && self.bytes == other.bytes)
+    }
+}
+
+impl Eq for ImageBytes {}
+
+impl Hash for ImageBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl<T: Into<Arc<[u8]>>> From<T> for ImageBytes {
+    fn from(value: T) -> Self {
+        Self::new(value.into())
+    }
+}
+
+impl ImageElement {
+    pub fn new(config: ImageConfig, data: ImageData, id: String) -> Self {
+        Self { config, data, id }
+    }
 
     pub fn config(&self) -> &ImageConfig {
         &self.config
     }
 
+    pub fn data(&self) -> &ImageData {
+        &self.data
+    }
+
     pub fn id(&self) -> &String {
         &self.id
     }
@@ -49,6 +49,7 @@ impl<T: RangeBounds<f32>> From<T> for MinMax {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Element {
     Container(ContainerElement),
 
@@ -58,16 +59,16 @@ pub enum Element {
 }
 
 impl Element {
-    pub fn container(config: ContainerConfig, id: Option<String>) -> Self {
+    pub fn container(config: ContainerConfig, id: String) -> Self {
         Self::Container(ContainerElement::new(config, id))
     }
 
-    pub fn text(config: TextConfig, data: String, id: Option<String>) -> Self {
+    pub fn text(config: TextConfig, data: String, id: String) -> Self {
         Self::Text(TextElement::new(config, data, id))
     }
 
-    pub fn image(config: TextConfig, data: String, id: Option<String>) -> Self {
-        Self::Text(TextElement::new(config, data, id))
+    pub fn image(config: ImageConfig, data: ImageData, id: String) -> Self {
+        Self::Image(ImageElement::new(config, data, id))
     }
 
     pub fn id(&self) -> &String {
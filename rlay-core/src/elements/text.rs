@@ -1,7 +1,6 @@
-use rand::RngCore;
-
-use crate::Color;
+use crate::{Color, Dimension2D, Point2D};
 use core::f32;
+use std::ops::Range;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum WrapMode {
@@ -17,7 +16,37 @@ pub enum TextAlignment {
     Left,
     Center,
     Right,
-    // Justify,
+    /// Spreads the words of the wrapped lines over the width of the text
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineHeight {
+    /// A multiple of the font size
+    Multiplier(f32),
+    Pixels(f32),
+}
+
+impl LineHeight {
+    /// The height of a line in pixels for this font size
+    pub fn resolve(&self, font_size: u16) -> f32 {
+        match self {
+            LineHeight::Multiplier(multiplier) => font_size as f32 * multiplier,
+            LineHeight::Pixels(pixels) => *pixels,
+        }
+    }
+}
+
+impl Default for LineHeight {
+    fn default() -> Self {
+        Self::Multiplier(1.0)
+    }
+}
+
+impl From<f32> for LineHeight {
+    fn from(value: f32) -> Self {
+        Self::Multiplier(value)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -26,8 +55,9 @@ pub struct TextConfig {
     pub font_id: u16,
     pub font_size: u16,
     pub font_name: Option<String>,
+    /// The space in pixels added between the characters
     pub letter_spacing: u16,
-    pub line_height: u16,
+    pub line_height: LineHeight,
     pub wrap_mode: WrapMode,
     pub text_alignment: TextAlignment,
 }
@@ -39,19 +69,45 @@ impl Default for TextConfig {
             font_id: Default::default(),
             font_size: 20,
             font_name: None,
-            letter_spacing: 1,
-            line_height: 1,
+            letter_spacing: 0,
+            line_height: LineHeight::default(),
             wrap_mode: WrapMode::default(),
             text_alignment: TextAlignment::default(),
         }
     }
 }
 
+/// A line of a text element, as it is drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    pub text: String,
+    /// Relative to the position of the text element
+    pub position: Point2D,
+    pub dimensions: Dimension2D,
+    /// The space added after each space of a justified line
+    pub word_spacing: f32,
+    /// The words of a justified line, which are drawn one by one to spread
+    /// them over the line. It is empty when the line is drawn as a whole.
+    pub words: Vec<TextWord>,
+}
+
+/// A word of a justified line
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextWord {
+    /// Where the word is in the text of the line
+    pub range: Range<usize>,
+    /// Relative to the position of the line
+    pub x: f32,
+    pub width: f32,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TextElement {
     pub id: String,
     pub config: TextConfig,
     pub data: String,
+    /// The lines of the text, known once the text is measured
+    pub lines: Vec<TextLine>,
 }
 
 pub struct TextDimensions {
@@ -61,11 +117,12 @@ pub struct TextDimensions {
 }
 
 impl TextElement {
-    pub fn new(config: TextConfig, data: String, id: Option<String>) -> Self {
+    pub fn new(config: TextConfig, data: String, id: String) -> Self {
         Self {
             config,
             data,
-            id: id.unwrap_or_else(|| rand::rng().next_u64().to_string()),
+            id,
+            lines: vec![],
         }
     }
 
@@ -80,4 +137,8 @@ impl TextElement {
     pub fn id(&self) -> &String {
         &self.id
     }
+
+    pub fn lines(&self) -> &[TextLine] {
+        &self.lines
+    }
 }
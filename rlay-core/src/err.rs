@@ -22,6 +22,12 @@ pub enum RlayError {
     #[error("Cannot find element in memory")]
     ElementNotFound,
 
+    #[error("Cannot get element because it was moved into the layout")]
+    ElementLaidOut,
+
+    #[error("Cannot load font: {0}")]
+    InvalidFont(String),
+
     #[error("{0}")]
     RuntimeError(String),
 
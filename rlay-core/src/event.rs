@@ -1,9 +1,119 @@
+use std::collections::HashMap;
 
+use crate::{AppState, Point2D, RlayKeyboardKey};
 
-pub enum Event {
-    Hover(HoverEvent)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
 }
 
-pub struct HoverEvent {
-    element_id: String,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// The cursor entered the element. It is sent to every element entered,
+    /// so it does not bubble.
+    PointerEnter,
+    /// The cursor left the element. It is sent to every element left, so it
+    /// does not bubble.
+    PointerLeave,
+    PointerDown(MouseButton),
+    PointerUp(MouseButton),
+    /// The left button was pressed and released over the element. The target
+    /// is the innermost element containing both where the button was pressed
+    /// and where it was released.
+    Click,
+    /// A second click on the same element, sent after its [`EventKind::Click`]
+    DoubleClick,
+    RightClick,
+    /// The movement of the mouse wheel, like [`crate::MouseInput::mouse_wheel`]
+    Wheel(Point2D),
+    /// A key pressed this frame, sent to the focused element, or to the root
+    /// when no element is focused
+    Key(RlayKeyboardKey),
+    /// A key held down long enough to be repeated, sent like
+    /// [`EventKind::Key`]
+    KeyRepeat(RlayKeyboardKey),
+    Focus,
+    Blur,
+}
+
+impl EventKind {
+    pub fn bubbles(&self) -> bool {
+        !matches!(self, Self::PointerEnter | Self::PointerLeave)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub kind: EventKind,
+    /// The element the event is aimed at
+    pub target: String,
+    /// The element whose handlers are called, the target or one of its
+    /// ancestors while the event bubbles up
+    pub current_target: String,
+    /// Where the cursor was when the event happened
+    pub position: Point2D,
+    propagation_stopped: bool,
+}
+
+impl Event {
+    pub fn new(kind: EventKind, target: String, position: Point2D) -> Self {
+        Self {
+            kind,
+            current_target: target.clone(),
+            target,
+            position,
+            propagation_stopped: false,
+        }
+    }
+
+    /// The handlers of the ancestors of the current target won't be called.
+    /// The other handlers of the current target are still called.
+    pub fn stop_propagation(&mut self) {
+        self.propagation_stopped = true;
+    }
+
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.propagation_stopped
+    }
+}
+
+pub type EventHandler = Box<dyn FnMut(&mut Event, &mut AppState)>;
+
+/// The handlers registered by element id. They are registered while building
+/// the view, and cleared at the start of every frame.
+#[derive(Default)]
+pub struct EventHandlers {
+    handlers: HashMap<String, Vec<EventHandler>>,
+}
+
+impl EventHandlers {
+    pub fn register(&mut self, element_id: &str, handler: EventHandler) {
+        self.handlers
+            .entry(element_id.to_owned())
+            .or_default()
+            .push(handler);
+    }
+
+    pub fn clear(&mut self) {
+        self.handlers.clear();
+    }
+
+    /// Calls the handlers of every element of `path`, from the target to the
+    /// root, until one of them stops the propagation
+    pub(crate) fn dispatch(&mut self, event: &mut Event, path: &[String], state: &mut AppState) {
+        for id in path {
+            event.current_target = id.clone();
+            if let Some(handlers) = self.handlers.get_mut(id) {
+                for handler in handlers {
+                    handler(event, state);
+                }
+            }
+
+            if event.is_propagation_stopped() || !event.kind.bubbles() {
+                break;
+            }
+        }
+    }
 }
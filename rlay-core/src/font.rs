@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use crate::TextConfig;
+
+/// Where a font is loaded from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FontSource {
+    File { path: String },
+    Bytes { bytes: Arc<[u8]> },
+}
+
+/// A font registered in the [`FontRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FontData {
+    /// The `font_id` of the texts using the font. The id `0` is the default
+    /// font, so registered fonts start at `1`.
+    pub id: u16,
+    pub name: String,
+    pub source: FontSource,
+}
+
+/// The fonts texts can refer to with their `font_name` or `font_id`.
+///
+/// The registry only knows where the fonts come from, each backend loads them
+/// the first time they are used.
+#[derive(Debug, Default)]
+pub struct FontRegistry {
+    fonts: Vec<FontData>,
+    /// The font of the texts without a registered font. Without it, they use
+    /// the default font of the backend.
+    default: Option<FontData>,
+}
+
+impl FontRegistry {
+    /// Registers the font file under `name`, returning its id
+    pub fn register_file(&mut self, name: &str, path: &str) -> u16 {
+        self.register(name, || FontSource::File {
+            path: path.to_string(),
+        })
+    }
+
+    /// Registers the content of a font file under `name`, returning its id
+    pub fn register_bytes(&mut self, name: &str, bytes: &[u8]) -> u16 {
+        self.register(name, || FontSource::Bytes {
+            bytes: bytes.into(),
+        })
+    }
+
+    /// Uses the font file for the texts without a registered font, in place
+    /// of the default font of the backend
+    pub fn set_default_file(&mut self, path: &str) {
+        self.default = Some(FontData {
+            id: 0,
+            name: "default".to_string(),
+            source: FontSource::File {
+                path: path.to_string(),
+            },
+        });
+    }
+
+    /// A name is only registered once, registering it again returns the id it
+    /// already has. Fonts can therefore be registered while building the view.
+    fn register(&mut self, name: &str, source: impl FnOnce() -> FontSource) -> u16 {
+        if let Some(font) = self.get_by_name(name) {
+            return font.id;
+        }
+
+        let id = self.fonts.len() as u16 + 1;
+        self.fonts.push(FontData {
+            id,
+            name: name.to_string(),
+            source: source(),
+        });
+        id
+    }
+
+    pub fn get(&self, id: u16) -> Option<&FontData> {
+        match id {
+            0 => self.default.as_ref(),
+            id => self.fonts.get(id as usize - 1),
+        }
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&FontData> {
+        self.fonts.iter().find(|font| font.name == name)
+    }
+
+    /// The font of the text, from its `font_name` or else its `font_id`, or
+    /// else the default font. `None` means the text uses the default font of
+    /// the backend.
+    pub fn resolve(&self, config: &TextConfig) -> Option<&FontData> {
+        config
+            .font_name
+            .as_deref()
+            .and_then(|name| self.get_by_name(name))
+            .or_else(|| self.get(config.font_id))
+            .or(self.default.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FontData> {
+        self.fonts.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved_name(fonts: &FontRegistry, config: &TextConfig) -> Option<String> {
+        fonts.resolve(config).map(|font| font.name.clone())
+    }
+
+    #[test]
+    fn resolve_the_texts_without_a_font_to_the_default_one() {
+        let mut fonts = FontRegistry::default();
+        let config = TextConfig::default();
+        assert_eq!(resolved_name(&fonts, &config), None);
+
+        fonts.set_default_file("default.ttf");
+        let roboto = fonts.register_file("Roboto", "roboto.ttf");
+        assert_eq!(resolved_name(&fonts, &config).as_deref(), Some("default"));
+
+        let with_id = TextConfig {
+            font_id: roboto,
+            ..Default::default()
+        };
+        assert_eq!(resolved_name(&fonts, &with_id).as_deref(), Some("Roboto"));
+        let missing = TextConfig {
+            font_name: Some("Missing".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolved_name(&fonts, &missing).as_deref(), Some("default"));
+    }
+}
@@ -4,9 +4,12 @@ use std::{
     ops::{Add, Div, Mul, Not, Sub},
 };
 
+use unicode_linebreak::BreakOpportunity;
+
 use crate::{
-    Alignment, AppCtx, ContainerConfig, ContainerElement, Element, LayoutDirection, MinMax, Sizing,
-    SizingAxis, TextElement, WrapMode, err::RlayError,
+    Alignment, AppCtx, ContainerConfig, Element, FloatingAttachTo, FloatingConfig, GridConfig,
+    GridSpan, GridTrack, LayoutDirection, MinMax, Sizing, SizingAxis, TextAlignment, TextConfig,
+    TextDimensions, TextLine, TextWord, WrapMode, err::RlayError,
 };
 
 macro_rules! def_states {
@@ -223,6 +226,35 @@ impl<S: ElementStep> ElementLayout<S> {
     pub fn children(&self) -> &[ElementLayout<S>] {
         &self.children
     }
+
+    /// Finds the element with this id, searching the element and its children
+    pub fn get_element_with_id(&self, id: &str) -> Option<&ElementLayout<S>> {
+        if self.element.id() == id {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .find_map(|child| child.get_element_with_id(id))
+    }
+
+    fn get_mut_element_with_id(&mut self, id: &str) -> Option<&mut ElementLayout<S>> {
+        if self.element.id() == id {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find_map(|child| child.get_mut_element_with_id(id))
+    }
+}
+
+impl ElementLayout<Done> {
+    /// Moves the element and all of its children
+    fn translate(&mut self, delta: Point2D) {
+        self.position = self.position + delta;
+        for child in self.children.iter_mut() {
+            child.translate(delta);
+        }
+    }
 }
 
 trait LayoutStep {
@@ -258,7 +290,6 @@ impl LayoutStep for ElementLayout<FitSizingWidth> {
         let children = self
             .children
             .into_iter()
-            .rev()
             .map(|child| child.apply_layout_step(ctx))
             .collect::<Result<_, _>>()?;
 
@@ -275,19 +306,7 @@ impl LayoutStep for ElementLayout<FitSizingWidth> {
                     });
                 };
 
-                let width = config.layout_direction.value_on_axis(
-                    children
-                        .iter()
-                        .map(|child| child.dimensions.width)
-                        .sum::<f32>()
-                        + ((children.len().max(1) - 1) as i32 * config.child_gap) as f32,
-                    children
-                        .iter()
-                        .map(|child| child.dimensions.width)
-                        .reduce(f32::max)
-                        .unwrap_or_default(),
-                ) + config.padding.val_x() as f32;
-
+                let width = fit_width(config, &children);
                 let parent_dimension =
                     (self.dimensions + Dimension2D::new(width, 0.0)).clamped_width(min_max);
 
@@ -299,14 +318,13 @@ impl LayoutStep for ElementLayout<FitSizingWidth> {
                     children,
                 })
             }
-            Element::Text(..) => Ok(ElementLayout {
+            Element::Text(..) | Element::Image(..) => Ok(ElementLayout {
                 _marker: PhantomData,
                 position: self.position,
                 dimensions: self.dimensions,
                 element: self.element,
                 children,
             }),
-            Element::Image(..) => todo!(),
         }
     }
 }
@@ -316,148 +334,131 @@ impl LayoutStep for ElementLayout<GrowShrinkSizingWidth> {
 
     fn apply_layout_step(self, ctx: &AppCtx) -> Result<ElementLayout<Self::NextStep>, RlayError> {
         let mut old_children = self.children;
-        let children;
-
-        old_children = old_children
-            .into_iter()
-            .map(|mut child| {
-                if let Element::Container(ContainerElement {
-                    config:
-                        ContainerConfig {
-                            sizing:
-                                Sizing {
-                                    width: SizingAxis::Percent(val),
-                                    ..
-                                },
-                            ..
-                        },
-                    ..
-                }) = child.data()
-                {
-                    child.dimensions.width = self.dimensions.width * *val;
-                }
-                child
-            })
-            .collect::<Box<[_]>>();
 
         if let Element::Container(ref container) = self.element {
             let config = container.config();
 
+            let nb_in_flow = old_children
+                .iter()
+                .filter(|child| !is_floating(child))
+                .count();
+            let children_gap = ((nb_in_flow.max(1) - 1) as i32 * config.child_gap) as f32;
+
+            let inner_width = self.dimensions.width - config.content_padding().val_x() as f32;
+            // The children of a wrapping container only share their line
+            let content_width = if config.wrap {
+                inner_width
+            } else {
+                inner_width - config.layout_direction.value_on_axis(children_gap, 0.0)
+            };
+
+            for child in old_children.iter_mut() {
+                // Floating children are not sharing the parent with their siblings
+                let available = if is_floating(child) {
+                    inner_width
+                } else {
+                    content_width
+                };
+                if let Some(width) =
+                    child_sizing(child).and_then(|sizing| sizing.width.resolve_percent(available))
+                {
+                    child.dimensions.width = width;
+                }
+            }
+
             let children_width = config.layout_direction.value_on_axis(
                 old_children
                     .iter()
+                    .filter(|child| !is_floating(child))
                     .map(|child| child.dimensions.width)
                     .sum::<f32>()
-                    + ((old_children.len().max(1) - 1) as i32 * config.child_gap) as f32,
+                    + children_gap,
                 0.0,
             );
 
-            let mut remaining_width =
-                self.dimensions.width - children_width - config.padding.val_x() as f32;
-
-            if let LayoutDirection::TopToBottom = config.layout_direction {
-                children = old_children
-                    .into_iter()
-                    .map(|mut child| {
-                        if let Element::Container(ContainerElement {
-                            config:
-                                ContainerConfig {
-                                    sizing:
-                                        Sizing {
-                                            width: SizingAxis::Grow(..),
-                                            ..
-                                        },
-                                    ..
-                                },
-                            ..
-                        }) = child.data()
-                        {
-                            child.dimensions.width = remaining_width;
-                        }
-                        child.apply_layout_step(ctx)
-                    })
-                    .collect::<Result<Box<[_]>, _>>()?;
+            let remaining_width = inner_width - children_width;
+
+            if let Some(grid) = &config.grid {
+                fill_grid_cells(
+                    grid,
+                    &mut old_children,
+                    LayoutDirection::LeftToRight,
+                    inner_width,
+                );
+            } else if let LayoutDirection::TopToBottom = config.layout_direction {
+                // The columns of a wrapping container are only known with the
+                // heights, so children grow to the width of the widest one
+                let grow_width = if config.wrap {
+                    old_children
+                        .iter()
+                        .filter(|child| {
+                            !is_floating(child)
+                                && !grows_on_axis(child, LayoutDirection::LeftToRight)
+                        })
+                        .map(|child| child.dimensions.width)
+                        .reduce(f32::max)
+                        .unwrap_or(remaining_width)
+                } else {
+                    remaining_width
+                };
+
+                for child in old_children.iter_mut().filter(|child| !is_floating(child)) {
+                    if grows_on_axis(child, LayoutDirection::LeftToRight) {
+                        child.dimensions.width = grow_width;
+                    }
+                }
+            } else if config.wrap {
+                let gap = config.child_gap as f32;
+                for line in wrap_lines(&old_children, config.layout_direction, inner_width, gap) {
+                    let line_width = line_size(&old_children, &line, config.layout_direction, gap);
+                    let children_grow = old_children
+                        .iter_mut()
+                        .enumerate()
+                        .filter(|(i, child)| {
+                            line.contains(i) && grows_on_axis(child, LayoutDirection::LeftToRight)
+                        })
+                        .map(|(_, child)| child)
+                        .collect();
+
+                    grow_children(
+                        children_grow,
+                        inner_width - line_width,
+                        LayoutDirection::LeftToRight,
+                    );
+                }
             } else {
-                let mut children_grow = old_children
+                let children_grow = old_children
                     .iter_mut()
                     .filter(|child| {
-                        matches!(
-                            child.data(),
-                            Element::Container(ContainerElement {
-                                config: ContainerConfig {
-                                    sizing: Sizing {
-                                        width: SizingAxis::Grow(..),
-                                        ..
-                                    },
-                                    ..
-                                },
-                                ..
-                            })
-                        )
+                        !is_floating(child) && grows_on_axis(child, LayoutDirection::LeftToRight)
                     })
-                    .collect::<Vec<_>>();
-
-                while remaining_width > 0.0 && !children_grow.is_empty() {
-                    let mut smallest = children_grow[0].dimensions.width;
-                    let mut second_smallest = f32::INFINITY;
-                    let mut width_to_add = remaining_width;
-
-                    for child in children_grow.iter() {
-                        if child.dimensions.width < smallest {
-                            second_smallest = smallest;
-                            smallest = child.dimensions.width;
-                        } else if child.dimensions.width > smallest {
-                            second_smallest = second_smallest.min(child.dimensions.width);
-                            width_to_add = second_smallest - smallest;
-                        }
-                    }
+                    .collect();
 
-                    width_to_add = width_to_add.min(remaining_width / children_grow.len() as f32);
-                    if width_to_add == 0.0 {
-                        break;
-                    }
-
-                    let mut child_rem_idx = vec![];
-
-                    for (i, child) in children_grow.iter_mut().enumerate() {
-                        let Element::Container(ref container) = child.element else {
-                            continue;
-                        };
-                        let config = container.config();
-                        let max = config.sizing.width.get_max();
-                        // let min = config.sizing.width.get_min();
-
-                        if child.dimensions.width == smallest {
-                            if child.dimensions.width + width_to_add > max {
-                                remaining_width -= max - child.dimensions.width;
-                                child.dimensions.width = max;
-                                child_rem_idx.push(i);
-                            } else {
-                                child.dimensions.width += width_to_add;
-                                remaining_width -= width_to_add;
-                            }
-                        }
-                    }
+                grow_children(children_grow, remaining_width, LayoutDirection::LeftToRight);
+            }
 
-                    children_grow = children_grow
-                        .into_iter()
-                        .enumerate()
-                        .filter_map(|(i, child)| child_rem_idx.contains(&i).not().then_some(child))
-                        .collect();
+            // A floating child grows to the size of its parent before being expanded
+            for child in old_children.iter_mut() {
+                let Some(floating) = floating_config(child) else {
+                    continue;
+                };
+                let expand = floating.expand.width;
+                if let Some(Sizing {
+                    width: SizingAxis::Grow(min_max),
+                    ..
+                }) = child_sizing(child)
+                {
+                    child.dimensions.width = min_max.clamp(inner_width);
                 }
-
-                children = old_children
-                    .into_iter()
-                    .map(|child| child.apply_layout_step(ctx))
-                    .collect::<Result<Box<[_]>, _>>()?;
+                child.dimensions.width += expand;
             }
-        } else {
-            children = old_children
-                .into_iter()
-                .map(|child| child.apply_layout_step(ctx))
-                .collect::<Result<Box<[_]>, _>>()?;
         }
 
+        let children = old_children
+            .into_iter()
+            .map(|child| child.apply_layout_step(ctx))
+            .collect::<Result<Box<[_]>, _>>()?;
+
         Ok(ElementLayout {
             _marker: PhantomData,
             position: self.position,
@@ -473,26 +474,49 @@ impl LayoutStep for ElementLayout<WrapText> {
 
     fn apply_layout_step(self, ctx: &AppCtx) -> Result<ElementLayout<Self::NextStep>, RlayError> {
         let padding_x = if let Element::Container(c) = self.data() {
-            c.config().padding.val_x()
+            c.config().content_padding().val_x()
         } else {
             0
         };
 
+        // The text in a grid wraps in its cell instead of the whole grid
+        let cell_widths = if let Element::Container(c) = self.data()
+            && let Some(grid) = &c.config().grid
+        {
+            let cells = grid_cells(grid, &self.children);
+            let columns = grid_track_sizes(
+                grid,
+                &cells,
+                &self.children,
+                LayoutDirection::LeftToRight,
+                Some(self.dimensions.width - padding_x as f32),
+            );
+            cells
+                .iter()
+                .map(|cell| {
+                    cell.map(|cell| {
+                        tracks_size(
+                            &columns[cell.column..cell.column + cell.span.columns],
+                            grid.column_gap as f32,
+                        )
+                    })
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
         let children = self
             .children
             .into_iter()
-            .map(|child| {
-                let (position, dimensions) = (child.position, child.dimensions);
+            .enumerate()
+            .map(|(i, child)| {
                 if let Element::Text(..) = child.element {
-                    let element = resize_text(ctx, child, &self.dimensions, padding_x as f32);
-
-                    return Ok(ElementLayout {
-                        _marker: PhantomData,
-                        position,
-                        dimensions,
-                        element: Element::Text(element),
-                        children: Box::new([]),
-                    });
+                    let available_width = match cell_widths.get(i) {
+                        Some(Some(width)) => *width,
+                        _ => self.dimensions.width - padding_x as f32,
+                    };
+                    return Ok(wrap_text(ctx, child, available_width));
                 }
 
                 child.apply_layout_step(ctx)
@@ -516,7 +540,6 @@ impl LayoutStep for ElementLayout<FitSizingHeight> {
         let children = self
             .children
             .into_iter()
-            .rev()
             .map(|child| child.apply_layout_step(ctx))
             .collect::<Result<Box<[_]>, _>>()?;
 
@@ -533,18 +556,44 @@ impl LayoutStep for ElementLayout<FitSizingHeight> {
                     });
                 };
 
-                let height = config.layout_direction.value_on_axis(
-                    children
+                let in_flow = children.iter().filter(|child| !is_floating(child));
+
+                let height = if let Some(grid) = &config.grid {
+                    let cells = grid_cells(grid, &children);
+                    let rows = grid_track_sizes(
+                        grid,
+                        &cells,
+                        &children,
+                        LayoutDirection::TopToBottom,
+                        None,
+                    );
+                    tracks_size(&rows, grid.row_gap as f32)
+                } else if config.wrap && config.layout_direction == LayoutDirection::LeftToRight {
+                    let lines = wrap_lines(
+                        &children,
+                        config.layout_direction,
+                        self.dimensions.width - config.content_padding().val_x() as f32,
+                        config.child_gap as f32,
+                    );
+                    lines
                         .iter()
-                        .map(|child| child.dimensions.height)
-                        .reduce(f32::max)
-                        .unwrap_or_default(),
-                    children
-                        .iter()
-                        .map(|child| child.dimensions.height)
+                        .map(|line| line_cross_size(&children, line, config.layout_direction))
                         .sum::<f32>()
-                        + ((children.len().max(1) - 1) as i32 * config.child_gap) as f32,
-                ) + config.padding.val_y() as f32;
+                        + ((lines.len().max(1) - 1) as i32 * config.line_gap) as f32
+                } else {
+                    config.layout_direction.value_on_axis(
+                        in_flow
+                            .clone()
+                            .map(|child| child.dimensions.height)
+                            .reduce(f32::max)
+                            .unwrap_or_default(),
+                        in_flow
+                            .clone()
+                            .map(|child| child.dimensions.height)
+                            .sum::<f32>()
+                            + ((in_flow.count().max(1) - 1) as i32 * config.child_gap) as f32,
+                    )
+                } + config.content_padding().val_y() as f32;
 
                 let parent_dimension =
                     (self.dimensions + Dimension2D::new(0.0, height)).clamped_height(min_max);
@@ -557,6 +606,28 @@ impl LayoutStep for ElementLayout<FitSizingHeight> {
                     children,
                 })
             }
+            Element::Image(ref image) => {
+                let config = image.config();
+                let mut dimensions = self.dimensions;
+
+                // Now that the width is known, a fit height keeps the aspect ratio
+                if let SizingAxis::Fit(min_max) = config.sizing.height
+                    && matches!(
+                        config.sizing.width,
+                        SizingAxis::Grow(..) | SizingAxis::Percent(..)
+                    )
+                {
+                    dimensions.height = min_max.clamp(config.height_for_width(dimensions.width));
+                }
+
+                Ok(ElementLayout {
+                    _marker: PhantomData,
+                    position: self.position,
+                    dimensions,
+                    element: self.element,
+                    children,
+                })
+            }
             Element::Text(..) => Ok(ElementLayout {
                 _marker: PhantomData,
                 position: self.position,
@@ -564,7 +635,6 @@ impl LayoutStep for ElementLayout<FitSizingHeight> {
                 element: self.element,
                 children,
             }),
-            Element::Image(..) => todo!(),
         }
     }
 }
@@ -574,151 +644,172 @@ impl LayoutStep for ElementLayout<GrowShrinkSizingHeight> {
 
     fn apply_layout_step(self, ctx: &AppCtx) -> Result<ElementLayout<Self::NextStep>, RlayError> {
         let mut old_children = self.children;
-        let children;
-
-        old_children = old_children
-            .into_iter()
-            .map(|mut child| {
-                if let Element::Container(ContainerElement {
-                    config:
-                        ContainerConfig {
-                            sizing:
-                                Sizing {
-                                    height: SizingAxis::Percent(val),
-                                    ..
-                                },
-                            ..
-                        },
-                    ..
-                }) = child.data()
-                {
-                    child.dimensions.height = self.dimensions.height * *val;
-                }
-                child
-            })
-            .collect::<Box<[_]>>();
 
         if let Element::Container(ref container) = self.element {
             let config = container.config();
 
+            let nb_in_flow = old_children
+                .iter()
+                .filter(|child| !is_floating(child))
+                .count();
+            let children_gap = ((nb_in_flow.max(1) - 1) as i32 * config.child_gap) as f32;
+
+            let inner_height = self.dimensions.height - config.content_padding().val_y() as f32;
+            let content_height = if config.wrap {
+                inner_height
+            } else {
+                inner_height - config.layout_direction.value_on_axis(0.0, children_gap)
+            };
+
+            for child in old_children.iter_mut() {
+                let available = if is_floating(child) {
+                    inner_height
+                } else {
+                    content_height
+                };
+                if let Some(height) =
+                    child_sizing(child).and_then(|sizing| sizing.height.resolve_percent(available))
+                {
+                    child.dimensions.height = height;
+                }
+            }
+
             let children_height = config.layout_direction.value_on_axis(
                 0.0,
                 old_children
                     .iter()
+                    .filter(|child| !is_floating(child))
                     .map(|child| child.dimensions.height)
                     .sum::<f32>()
-                    + ((old_children.len().max(1) - 1) as i32 * config.child_gap) as f32,
+                    + children_gap,
             );
 
-            let mut remaining_height =
-                self.dimensions.height - children_height - config.padding.val_y() as f32;
-
-            if let LayoutDirection::LeftToRight = config.layout_direction {
-                children = old_children
-                    .into_iter()
-                    .map(|mut child| {
-                        if let Element::Container(ContainerElement {
-                            config:
-                                ContainerConfig {
-                                    sizing:
-                                        Sizing {
-                                            height: SizingAxis::Grow(..),
-                                            ..
-                                        },
-                                    ..
-                                },
-                            ..
-                        }) = child.data()
-                        {
+            let remaining_height = inner_height - children_height;
+
+            if let Some(grid) = &config.grid {
+                fill_grid_cells(
+                    grid,
+                    &mut old_children,
+                    LayoutDirection::TopToBottom,
+                    inner_height,
+                );
+            } else if let LayoutDirection::LeftToRight = config.layout_direction {
+                if config.wrap {
+                    // Children growing across the lines take the height of their line
+                    let lines = wrap_lines(
+                        &old_children,
+                        config.layout_direction,
+                        self.dimensions.width - config.content_padding().val_x() as f32,
+                        config.child_gap as f32,
+                    );
+                    for line in lines {
+                        let line_height =
+                            line_cross_size(&old_children, &line, config.layout_direction);
+                        for i in line {
+                            let child = &mut old_children[i];
+                            if let Some(Sizing {
+                                height: SizingAxis::Grow(min_max),
+                                ..
+                            }) = child_sizing(child)
+                            {
+                                child.dimensions.height = min_max.clamp(line_height);
+                            }
+                        }
+                    }
+                } else {
+                    for child in old_children.iter_mut().filter(|child| !is_floating(child)) {
+                        if grows_on_axis(child, LayoutDirection::TopToBottom) {
                             child.dimensions.height = remaining_height;
                         }
-                        child.apply_layout_step(ctx)
-                    })
-                    .collect::<Result<Box<[_]>, _>>()?;
+                    }
+                }
+            } else if config.wrap {
+                let gap = config.child_gap as f32;
+                for line in wrap_lines(&old_children, config.layout_direction, inner_height, gap) {
+                    let line_height = line_size(&old_children, &line, config.layout_direction, gap);
+                    let children_grow = old_children
+                        .iter_mut()
+                        .enumerate()
+                        .filter(|(i, child)| {
+                            line.contains(i) && grows_on_axis(child, LayoutDirection::TopToBottom)
+                        })
+                        .map(|(_, child)| child)
+                        .collect();
+
+                    grow_children(
+                        children_grow,
+                        inner_height - line_height,
+                        LayoutDirection::TopToBottom,
+                    );
+                }
             } else {
-                let mut children_grow = old_children
+                let children_grow = old_children
                     .iter_mut()
                     .filter(|child| {
-                        matches!(
-                            child.data(),
-                            Element::Container(ContainerElement {
-                                config: ContainerConfig {
-                                    sizing: Sizing {
-                                        height: SizingAxis::Grow(..),
-                                        ..
-                                    },
-                                    ..
-                                },
-                                ..
-                            })
-                        )
+                        !is_floating(child) && grows_on_axis(child, LayoutDirection::TopToBottom)
                     })
-                    .collect::<Vec<_>>();
-                while remaining_height > 0.0 && !children_grow.is_empty() {
-                    let mut smallest = children_grow[0].dimensions.height;
-                    let mut second_smallest = f32::INFINITY;
-                    let mut height_to_add = remaining_height;
-
-                    for child in children_grow.iter() {
-                        if child.dimensions.height < smallest {
-                            second_smallest = smallest;
-                            smallest = child.dimensions.height;
-                        } else if child.dimensions.height > smallest {
-                            second_smallest = second_smallest.min(child.dimensions.height);
-                            height_to_add = second_smallest - smallest;
-                        }
-                    }
+                    .collect();
 
-                    height_to_add =
-                        height_to_add.min(remaining_height / children_grow.len() as f32);
-                    if height_to_add == 0.0 {
-                        break;
-                    }
+                grow_children(
+                    children_grow,
+                    remaining_height,
+                    LayoutDirection::TopToBottom,
+                );
+            }
 
-                    let mut child_rem_idx = vec![];
-
-                    for (i, child) in children_grow.iter_mut().enumerate() {
-                        let Element::Container(ref container) = child.element else {
-                            continue;
-                        };
-                        let config = container.config();
-                        let max = config.sizing.height.get_max();
-                        // let min = config.sizing.height.get_min();
-
-                        if child.dimensions.height == smallest {
-                            if child.dimensions.height + height_to_add > max {
-                                remaining_height -= max - child.dimensions.height;
-                                child.dimensions.height = max;
-                                child_rem_idx.push(i);
-                            } else {
-                                child.dimensions.height += height_to_add;
-                                remaining_height -= height_to_add;
-                            }
-                        }
-                    }
+            for child in old_children.iter_mut() {
+                let Some(floating) = floating_config(child) else {
+                    continue;
+                };
+                let expand = floating.expand.height;
+                if let Some(Sizing {
+                    height: SizingAxis::Grow(min_max),
+                    ..
+                }) = child_sizing(child)
+                {
+                    child.dimensions.height = min_max.clamp(inner_height);
+                }
+                child.dimensions.height += expand;
+            }
+        }
 
-                    children_grow = children_grow
-                        .into_iter()
-                        .enumerate()
-                        .filter_map(|(i, child)| child_rem_idx.contains(&i).not().then_some(child))
-                        .collect();
+        let children = old_children
+            .into_iter()
+            .map(|child| child.apply_layout_step(ctx))
+            .collect::<Result<Box<[_]>, _>>()?;
+
+        let mut dimensions = self.dimensions;
+
+        match self.element {
+            // The height of the image is now known, a fit width keeps the aspect ratio
+            Element::Image(ref image) => {
+                let config = image.config();
+                if let SizingAxis::Fit(min_max) = config.sizing.width
+                    && matches!(
+                        config.sizing.height,
+                        SizingAxis::Grow(..) | SizingAxis::Percent(..)
+                    )
+                {
+                    dimensions.width = min_max.clamp(config.width_for_height(dimensions.height));
                 }
-                children = old_children
-                    .into_iter()
-                    .map(|child| child.apply_layout_step(ctx))
-                    .collect::<Result<Box<[_]>, _>>()?;
             }
-        } else {
-            children = old_children
-                .into_iter()
-                .map(|child| child.apply_layout_step(ctx))
-                .collect::<Result<Box<[_]>, _>>()?;
+            // Such an image may have widened, so the containers fitting it
+            // widen too
+            Element::Container(ref container) => {
+                let config = container.config();
+                if let SizingAxis::Fit(min_max) = config.sizing.width {
+                    dimensions.width = dimensions
+                        .width
+                        .max(min_max.clamp(fit_width(config, &children)));
+                }
+            }
+            Element::Text(..) => {}
         }
 
         Ok(ElementLayout {
             _marker: PhantomData,
             position: self.position,
-            dimensions: self.dimensions,
+            dimensions,
             element: self.element,
             children,
         })
@@ -736,8 +827,41 @@ impl LayoutStep for ElementLayout<Positions> {
         // let parent_dim = self.dimensions;
         let children;
 
-        if let Element::Container(ref container) = self.element {
+        if let Element::Container(ref container) = self.element
+            && (container.config().wrap || container.config().grid.is_some())
+        {
+            let config = container.config();
+            let scroll_offset = config
+                .scroll
+                .restrict(app_ctx.get_scroll_offset(container.id()));
+            let padding = config.content_padding();
+            let content_position = parent_position
+                + Point2D::new(padding.left as f32, padding.top as f32)
+                + scroll_offset;
+            let offsets = match &config.grid {
+                Some(grid) => grid_children_offsets(config, grid, self.dimensions, &self.children),
+                None => wrapped_children_offsets(config, self.dimensions, &self.children),
+            };
+
+            children = self
+                .children
+                .into_iter()
+                .zip(offsets)
+                .map(|(mut child, offset)| {
+                    child.position = match offset {
+                        Some(offset) => child.position + content_position + offset,
+                        // Floating children are placed once the whole layout is known
+                        None => parent_position,
+                    };
+                    child.apply_layout_step(app_ctx)
+                })
+                .collect::<Result<Box<[_]>, _>>()?;
+        } else if let Element::Container(ref container) = self.element {
             let config = container.config();
+            let scroll_offset = config
+                .scroll
+                .restrict(app_ctx.get_scroll_offset(container.id()));
+
             struct Offsets {
                 offset: Point2D,
             }
@@ -759,14 +883,14 @@ impl LayoutStep for ElementLayout<Positions> {
                 },
             );
 
+            let in_flow = self.children.iter().filter(|c| !is_floating(c));
+            let nb_in_flow = in_flow.clone().count();
+
             let total_width = config.layout_direction.value_on_axis(
-                self.children
-                    .iter()
-                    .map(|c| c.dimensions.width)
-                    .sum::<f32>()
-                    + config.child_gap as f32 * (self.children.len().max(1) - 1) as f32,
-                self.children
-                    .iter()
+                in_flow.clone().map(|c| c.dimensions.width).sum::<f32>()
+                    + config.child_gap as f32 * (nb_in_flow.max(1) - 1) as f32,
+                in_flow
+                    .clone()
                     .map(|c| (c.dimensions.width * 100.0) as i32)
                     .max()
                     .unwrap_or(0) as f32
@@ -784,17 +908,14 @@ impl LayoutStep for ElementLayout<Positions> {
             }
 
             let total_height = config.layout_direction.value_on_axis(
-                self.children
-                    .iter()
+                in_flow
+                    .clone()
                     .map(|c| (c.dimensions.height * 100.0) as i32)
                     .max()
                     .unwrap_or(0) as f32
                     / 100.0,
-                self.children
-                    .iter()
-                    .map(|c| c.dimensions.height)
-                    .sum::<f32>()
-                    + config.child_gap as f32 * (self.children.len().max(1) - 1) as f32,
+                in_flow.map(|c| c.dimensions.height).sum::<f32>()
+                    + config.child_gap as f32 * (nb_in_flow.max(1) - 1) as f32,
             );
 
             match config.align.y {
@@ -811,6 +932,13 @@ impl LayoutStep for ElementLayout<Positions> {
                 .children
                 .into_iter()
                 .scan(&mut step_ctx, |ctx, mut child| {
+                    // Floating children are placed once the whole layout is
+                    // known, see `position_floating_elements`
+                    if is_floating(&child) {
+                        child.position = parent_position;
+                        return Some(child.apply_layout_step(app_ctx));
+                    }
+
                     let offset = &ctx;
 
                     match config.align.x {
@@ -859,6 +987,8 @@ impl LayoutStep for ElementLayout<Positions> {
                         }
                     }
 
+                    child.position = child.position + scroll_offset;
+
                     let layout = child.apply_layout_step(app_ctx);
                     let Ok(layout) = layout else {
                         return Some(layout);
@@ -891,105 +1021,1510 @@ impl LayoutStep for ElementLayout<Positions> {
     }
 }
 
-fn resize_text(
+/// The width of the children of the container, with its padding, which is the
+/// width of a container fitting them
+fn fit_width<S: ElementStep>(config: &ContainerConfig, children: &[ElementLayout<S>]) -> f32 {
+    let in_flow = children.iter().filter(|child| !is_floating(child));
+
+    let width = if let Some(grid) = &config.grid {
+        let cells = grid_cells(grid, children);
+        let columns = grid_track_sizes(grid, &cells, children, LayoutDirection::LeftToRight, None);
+        tracks_size(&columns, grid.column_gap as f32)
+    } else {
+        config.layout_direction.value_on_axis(
+            in_flow
+                .clone()
+                .map(|child| child.dimensions.width)
+                .sum::<f32>()
+                + ((in_flow.clone().count().max(1) - 1) as i32 * config.child_gap) as f32,
+            in_flow
+                .map(|child| child.dimensions.width)
+                .reduce(f32::max)
+                .unwrap_or_default(),
+        )
+    };
+    width + config.content_padding().val_x() as f32
+}
+
+/// The sizing of the element, if it has one
+fn child_sizing<S: ElementStep>(child: &ElementLayout<S>) -> Option<Sizing> {
+    match child.data() {
+        Element::Container(container) => Some(container.config().sizing),
+        Element::Image(image) => Some(image.config().sizing),
+        Element::Text(..) => None,
+    }
+}
+
+/// How far each child of a wrapping container is from the top left corner of
+/// its content box. Floating children, which are not in a line, have no offset.
+fn wrapped_children_offsets(
+    config: &ContainerConfig,
+    dimensions: Dimension2D,
+    children: &[ElementLayout<Positions>],
+) -> Vec<Option<Point2D>> {
+    let direction = config.layout_direction;
+    let gap = config.child_gap as f32;
+
+    let inner = Dimension2D::new(
+        dimensions.width - config.content_padding().val_x() as f32,
+        dimensions.height - config.content_padding().val_y() as f32,
+    );
+    let inner_main = direction.value_on_axis(inner.width, inner.height);
+    let inner_cross = direction.value_on_axis(inner.height, inner.width);
+
+    let (main_align, cross_align) = direction.value_on_axis(
+        (config.align.x, config.align.y),
+        (config.align.y, config.align.x),
+    );
+
+    let lines = wrap_lines(children, direction, inner_main, gap);
+    let line_cross_sizes = lines
+        .iter()
+        .map(|line| line_cross_size(children, line, direction))
+        .collect::<Vec<_>>();
+    let lines_cross_size = line_cross_sizes.iter().sum::<f32>()
+        + ((lines.len().max(1) - 1) as i32 * config.line_gap) as f32;
+
+    let mut offsets = vec![None; children.len()];
+    let mut cross_offset = alignment_factor(cross_align) * (inner_cross - lines_cross_size);
+
+    for (line, line_cross) in lines.iter().zip(line_cross_sizes) {
+        let mut main_offset =
+            alignment_factor(main_align) * (inner_main - line_size(children, line, direction, gap));
+
+        for &i in line {
+            let child_dim = children[i].dimensions;
+            let child_cross = direction.value_on_axis(child_dim.height, child_dim.width);
+            let child_cross_offset =
+                cross_offset + alignment_factor(cross_align) * (line_cross - child_cross);
+
+            offsets[i] = Some(direction.value_on_axis(
+                Point2D::new(main_offset, child_cross_offset),
+                Point2D::new(child_cross_offset, main_offset),
+            ));
+
+            main_offset += direction.value_on_axis(child_dim.width, child_dim.height) + gap;
+        }
+
+        cross_offset += line_cross + config.line_gap as f32;
+    }
+
+    offsets
+}
+
+/// Where the content is placed in the free space, from the start (0) to the end (1)
+fn alignment_factor(alignment: Alignment) -> f32 {
+    match alignment {
+        Alignment::Start => 0.0,
+        Alignment::Center => 0.5,
+        Alignment::End | Alignment::EndReverse => 1.0,
+    }
+}
+
+/// Whether the child grows along the axis of the direction
+fn grows_on_axis<S: ElementStep>(child: &ElementLayout<S>, axis: LayoutDirection) -> bool {
+    child_sizing(child).is_some_and(|sizing| {
+        matches!(
+            axis.value_on_axis(sizing.width, sizing.height),
+            SizingAxis::Grow(..)
+        )
+    })
+}
+
+/// Shares the remaining space between the children growing along the axis.
+/// The smallest children grow first, until they are as big as the next
+/// smallest ones or reach their max size.
+fn grow_children<S: ElementStep>(
+    mut children_grow: Vec<&mut ElementLayout<S>>,
+    mut remaining: f32,
+    axis: LayoutDirection,
+) {
+    let size_of = |child: &ElementLayout<S>| {
+        axis.value_on_axis(child.dimensions.width, child.dimensions.height)
+    };
+
+    while remaining > 0.0 && !children_grow.is_empty() {
+        let mut smallest = size_of(children_grow[0]);
+        let mut second_smallest = f32::INFINITY;
+        let mut size_to_add = remaining;
+
+        for child in children_grow.iter() {
+            let size = size_of(child);
+            if size < smallest {
+                second_smallest = smallest;
+                smallest = size;
+            } else if size > smallest {
+                second_smallest = second_smallest.min(size);
+                size_to_add = second_smallest - smallest;
+            }
+        }
+
+        size_to_add = size_to_add.min(remaining / children_grow.len() as f32);
+        if size_to_add == 0.0 {
+            break;
+        }
+
+        let mut child_rem_idx = vec![];
+
+        for (i, child) in children_grow.iter_mut().enumerate() {
+            let Some(sizing) = child_sizing(child) else {
+                continue;
+            };
+            let max = axis.value_on_axis(sizing.width, sizing.height).get_max();
+            let size = size_of(child);
+
+            if size == smallest {
+                let new_size = if size + size_to_add > max {
+                    remaining -= max - size;
+                    child_rem_idx.push(i);
+                    max
+                } else {
+                    remaining -= size_to_add;
+                    size + size_to_add
+                };
+
+                match axis {
+                    LayoutDirection::LeftToRight => child.dimensions.width = new_size,
+                    LayoutDirection::TopToBottom => child.dimensions.height = new_size,
+                }
+            }
+        }
+
+        children_grow = children_grow
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, child)| child_rem_idx.contains(&i).not().then_some(child))
+            .collect();
+    }
+}
+
+/// Splits the children of a wrapping container in lines along the direction,
+/// as indices in `children`. Floating children are not part of any line.
+fn wrap_lines<S: ElementStep>(
+    children: &[ElementLayout<S>],
+    direction: LayoutDirection,
+    available: f32,
+    gap: f32,
+) -> Vec<Vec<usize>> {
+    // The lines are computed again in later steps, once grown children fill
+    // their line. This keeps rounding errors from moving them to the next one.
+    const TOLERANCE: f32 = 0.01;
+
+    let mut lines: Vec<Vec<usize>> = vec![];
+    let mut line_size = 0.0;
+
+    for (i, child) in children.iter().enumerate() {
+        if is_floating(child) {
+            continue;
+        }
+        let size = direction.value_on_axis(child.dimensions.width, child.dimensions.height);
+
+        match lines.last_mut() {
+            Some(line) if line_size + gap + size <= available + TOLERANCE => {
+                line.push(i);
+                line_size += gap + size;
+            }
+            _ => {
+                lines.push(vec![i]);
+                line_size = size;
+            }
+        }
+    }
+
+    lines
+}
+
+/// The size of a line along the direction, gaps included
+fn line_size<S: ElementStep>(
+    children: &[ElementLayout<S>],
+    line: &[usize],
+    direction: LayoutDirection,
+    gap: f32,
+) -> f32 {
+    line.iter()
+        .map(|i| {
+            direction.value_on_axis(
+                children[*i].dimensions.width,
+                children[*i].dimensions.height,
+            )
+        })
+        .sum::<f32>()
+        + gap * (line.len().max(1) - 1) as f32
+}
+
+/// The size of a line across the direction, the size of its biggest child
+fn line_cross_size<S: ElementStep>(
+    children: &[ElementLayout<S>],
+    line: &[usize],
+    direction: LayoutDirection,
+) -> f32 {
+    line.iter()
+        .map(|i| {
+            direction.value_on_axis(
+                children[*i].dimensions.height,
+                children[*i].dimensions.width,
+            )
+        })
+        .reduce(f32::max)
+        .unwrap_or_default()
+}
+
+/// The tracks taken by a child of a grid container
+#[derive(Debug, Clone, Copy)]
+struct GridCell {
+    column: usize,
+    row: usize,
+    span: GridSpan,
+}
+
+impl GridCell {
+    /// The first track and the number of tracks taken along the axis
+    fn on_axis(&self, axis: LayoutDirection) -> (usize, usize) {
+        axis.value_on_axis((self.column, self.span.columns), (self.row, self.span.rows))
+    }
+}
+
+/// The cells taken by the element when its parent is a grid
+fn grid_span<S: ElementStep>(child: &ElementLayout<S>) -> GridSpan {
+    match child.data() {
+        Element::Container(container) => container.config().grid_span,
+        Element::Text(..) | Element::Image(..) => GridSpan::default(),
+    }
+}
+
+/// Places the children of a grid container row by row, each one in the first
+/// free cells after the previous one. Floating children are not in the grid.
+fn grid_cells<S: ElementStep>(
+    grid: &GridConfig,
+    children: &[ElementLayout<S>],
+) -> Vec<Option<GridCell>> {
+    let nb_columns = grid.columns.len().max(1);
+    let mut taken: Vec<Vec<bool>> = vec![];
+    let (mut row, mut column) = (0, 0);
+
+    children
+        .iter()
+        .map(|child| {
+            if is_floating(child) {
+                return None;
+            }
+            let span = grid_span(child);
+            let span = GridSpan::new(span.columns.clamp(1, nb_columns), span.rows.max(1));
+
+            loop {
+                if column + span.columns > nb_columns {
+                    column = 0;
+                    row += 1;
+                }
+                let is_free = (row..row + span.rows).all(|r| {
+                    (column..column + span.columns)
+                        .all(|c| !taken.get(r).is_some_and(|taken_row| taken_row[c]))
+                });
+                if is_free {
+                    break;
+                }
+                column += 1;
+            }
+
+            if taken.len() < row + span.rows {
+                taken.resize(row + span.rows, vec![false; nb_columns]);
+            }
+            for taken_row in &mut taken[row..row + span.rows] {
+                taken_row[column..column + span.columns].fill(true);
+            }
+
+            let cell = GridCell { column, row, span };
+            column += span.columns;
+            Some(cell)
+        })
+        .collect()
+}
+
+/// The sizes of the columns or the rows of a grid, depending on the axis.
+/// Without the `available` size, growing tracks fit their children and
+/// percent tracks are empty, like their elements when fitting the parent.
+fn grid_track_sizes<S: ElementStep>(
+    grid: &GridConfig,
+    cells: &[Option<GridCell>],
+    children: &[ElementLayout<S>],
+    axis: LayoutDirection,
+    available: Option<f32>,
+) -> Vec<f32> {
+    let (tracks, gap) = axis.value_on_axis(
+        (&grid.columns, grid.column_gap as f32),
+        (&grid.rows, grid.row_gap as f32),
+    );
+    let nb_tracks = cells
+        .iter()
+        .flatten()
+        .map(|cell| {
+            let (start, span) = cell.on_axis(axis);
+            start + span
+        })
+        .max()
+        .unwrap_or_default()
+        .max(tracks.len());
+    let track = |i: usize| tracks.get(i).copied().unwrap_or_default();
+    let fits_content = |i: usize| match track(i) {
+        GridTrack::Fit(..) => true,
+        GridTrack::Grow(..) => available.is_none(),
+        GridTrack::Fixed(..) | GridTrack::Percent(..) => false,
+    };
+    let size_of = |child: &ElementLayout<S>| {
+        axis.value_on_axis(child.dimensions.width, child.dimensions.height)
+    };
+
+    let mut sizes = (0..nb_tracks)
+        .map(|i| match track(i) {
+            GridTrack::Fixed(size) => size,
+            GridTrack::Percent(percent) => available.map_or(0.0, |size| size.max(0.0) * percent),
+            GridTrack::Fit(..) | GridTrack::Grow(..) => 0.0,
+        })
+        .collect::<Vec<_>>();
+
+    // Children growing or sized in percent take the size of their cell, so
+    // they can't be used to size it
+    let in_grid = || {
+        cells
+            .iter()
+            .zip(children)
+            .filter(|(_, child)| {
+                !child_sizing(child).is_some_and(|sizing| {
+                    matches!(
+                        axis.value_on_axis(sizing.width, sizing.height),
+                        SizingAxis::Grow(..) | SizingAxis::Percent(..)
+                    )
+                })
+            })
+            .filter_map(|(cell, child)| Some((cell.as_ref()?.on_axis(axis), child)))
+    };
+
+    for ((start, span), child) in in_grid() {
+        if span == 1 && fits_content(start) {
+            sizes[start] = sizes[start].max(size_of(child));
+        }
+    }
+
+    // A child spanning many tracks shares what it is missing between the
+    // spanned tracks that fit their children
+    for ((start, span), child) in in_grid().filter(|((_, span), _)| *span > 1) {
+        let missing = size_of(child) - tracks_size(&sizes[start..start + span], gap);
+        let fitting = (start..start + span)
+            .filter(|i| fits_content(*i))
+            .collect::<Vec<_>>();
+        if missing > 0.0 && !fitting.is_empty() {
+            for i in fitting.iter() {
+                sizes[*i] += missing / fitting.len() as f32;
+            }
+        }
+    }
+
+    for (i, size) in sizes.iter_mut().enumerate() {
+        if let GridTrack::Fit(min_max) = track(i) {
+            *size = min_max.clamp(*size);
+        }
+    }
+
+    if let Some(available) = available {
+        let total_grow = (0..nb_tracks)
+            .filter_map(|i| match track(i) {
+                GridTrack::Grow(factor) => Some(factor),
+                _ => None,
+            })
+            .sum::<f32>();
+        let remaining = available
+            - (0..nb_tracks)
+                .filter(|i| !matches!(track(*i), GridTrack::Grow(..)))
+                .map(|i| sizes[i])
+                .sum::<f32>()
+            - gap * (nb_tracks.max(1) - 1) as f32;
+
+        if total_grow > 0.0 {
+            for (i, size) in sizes.iter_mut().enumerate() {
+                if let GridTrack::Grow(factor) = track(i) {
+                    *size = remaining.max(0.0) * factor / total_grow;
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+/// The size of consecutive tracks, gaps included
+fn tracks_size(sizes: &[f32], gap: f32) -> f32 {
+    sizes.iter().sum::<f32>() + gap * (sizes.len().max(1) - 1) as f32
+}
+
+/// How far the track is from the start of the grid
+fn track_offset(sizes: &[f32], index: usize, gap: f32) -> f32 {
+    sizes[..index].iter().sum::<f32>() + gap * index as f32
+}
+
+/// Sizes the children of a grid growing along the axis to fill their cell
+fn fill_grid_cells<S: ElementStep>(
+    grid: &GridConfig,
+    children: &mut [ElementLayout<S>],
+    axis: LayoutDirection,
+    available: f32,
+) {
+    let gap = axis.value_on_axis(grid.column_gap, grid.row_gap) as f32;
+    let cells = grid_cells(grid, children);
+    let sizes = grid_track_sizes(grid, &cells, children, axis, Some(available));
+
+    for (cell, child) in cells.iter().zip(children.iter_mut()) {
+        let Some(cell) = cell else {
+            continue;
+        };
+        let Some(sizing) = child_sizing(child) else {
+            continue;
+        };
+        let (start, span) = cell.on_axis(axis);
+        let cell_size = tracks_size(&sizes[start..start + span], gap);
+
+        let size = match axis.value_on_axis(sizing.width, sizing.height) {
+            SizingAxis::Grow(min_max) => min_max.clamp(cell_size),
+            sizing @ SizingAxis::Percent(..) => {
+                sizing.resolve_percent(cell_size).unwrap_or_default()
+            }
+            SizingAxis::Fit(..) | SizingAxis::Fixed(..) => continue,
+        };
+        match axis {
+            LayoutDirection::LeftToRight => child.dimensions.width = size,
+            LayoutDirection::TopToBottom => child.dimensions.height = size,
+        }
+    }
+}
+
+/// How far each child of a grid container is from the top left corner of its
+/// content box. The grid and the children in their cells follow the alignment
+/// of the container.
+fn grid_children_offsets(
+    config: &ContainerConfig,
+    grid: &GridConfig,
+    dimensions: Dimension2D,
+    children: &[ElementLayout<Positions>],
+) -> Vec<Option<Point2D>> {
+    let (column_gap, row_gap) = (grid.column_gap as f32, grid.row_gap as f32);
+    let inner = Dimension2D::new(
+        dimensions.width - config.content_padding().val_x() as f32,
+        dimensions.height - config.content_padding().val_y() as f32,
+    );
+
+    let cells = grid_cells(grid, children);
+    let columns = grid_track_sizes(
+        grid,
+        &cells,
+        children,
+        LayoutDirection::LeftToRight,
+        Some(inner.width),
+    );
+    let rows = grid_track_sizes(
+        grid,
+        &cells,
+        children,
+        LayoutDirection::TopToBottom,
+        Some(inner.height),
+    );
+
+    let grid_offset = Point2D::new(
+        alignment_factor(config.align.x) * (inner.width - tracks_size(&columns, column_gap)),
+        alignment_factor(config.align.y) * (inner.height - tracks_size(&rows, row_gap)),
+    );
+
+    cells
+        .iter()
+        .zip(children)
+        .map(|(cell, child)| {
+            let cell = cell.as_ref()?;
+            let cell_position = Point2D::new(
+                track_offset(&columns, cell.column, column_gap),
+                track_offset(&rows, cell.row, row_gap),
+            );
+            let cell_dimensions = Dimension2D::new(
+                tracks_size(
+                    &columns[cell.column..cell.column + cell.span.columns],
+                    column_gap,
+                ),
+                tracks_size(&rows[cell.row..cell.row + cell.span.rows], row_gap),
+            );
+            let align_offset = Point2D::new(
+                alignment_factor(config.align.x) * (cell_dimensions.width - child.dimensions.width),
+                alignment_factor(config.align.y)
+                    * (cell_dimensions.height - child.dimensions.height),
+            );
+
+            Some(grid_offset + cell_position + align_offset)
+        })
+        .collect()
+}
+
+/// The floating config of the element, if it is a floating container
+fn floating_config<S: ElementStep>(child: &ElementLayout<S>) -> Option<&FloatingConfig> {
+    match child.data() {
+        Element::Container(container) => container.config().floating.as_ref(),
+        Element::Text(..) | Element::Image(..) => None,
+    }
+}
+
+/// Floating elements are taken out of the flow of their parent
+fn is_floating<S: ElementStep>(child: &ElementLayout<S>) -> bool {
+    floating_config(child).is_some()
+}
+
+/// Floating elements can be attached to any element of the tree, so they are
+/// positioned once every other element is in place. They are handled from the
+/// root down, which lets a floating element attached to another one follow it.
+fn position_floating_elements(root: &mut ElementLayout<Done>) {
+    let mut floating = vec![];
+    collect_floating_ids(root, None, &mut floating);
+
+    for (id, parent_id) in floating {
+        let Some(element) = root.get_element_with_id(&id) else {
+            continue;
+        };
+        let Some(config) = floating_config(element) else {
+            continue;
+        };
+
+        let target = match &config.attach_to {
+            FloatingAttachTo::Parent => {
+                parent_id.and_then(|parent_id| root.get_element_with_id(&parent_id))
+            }
+            FloatingAttachTo::ElementWithId(target_id) => root.get_element_with_id(target_id),
+            FloatingAttachTo::Root => Some(&*root),
+        };
+        let Some(target) = target else {
+            continue;
+        };
+
+        let position = target.position + config.attach_point.parent.offset_in(target.dimensions)
+            - config.attach_point.element.offset_in(element.dimensions)
+            + config.offset;
+        let delta = position - element.position;
+
+        if let Some(element) = root.get_mut_element_with_id(&id) {
+            element.translate(delta);
+        }
+    }
+}
+
+/// Collects the ids of the floating elements with the id of their parent
+fn collect_floating_ids(
+    element: &ElementLayout<Done>,
+    parent_id: Option<&String>,
+    ids: &mut Vec<(String, Option<String>)>,
+) {
+    if is_floating(element) {
+        ids.push((element.element.id().clone(), parent_id.cloned()));
+    }
+    for child in element.children() {
+        collect_floating_ids(child, Some(element.element.id()), ids);
+    }
+}
+
+/// Wraps the words of the text which are overflowing the available width. The
+/// wrapped text takes the available width, its lines are aligned in it.
+fn wrap_text(
     ctx: &AppCtx,
     element: ElementLayout<WrapText>,
-    parent_dim: &Dimension2D,
-    padding_x: f32,
-) -> TextElement {
-    let text_dimensions = element.dimensions();
-
-    let Element::Text(text) = element.element else {
+    available_width: f32,
+) -> ElementLayout<FitSizingHeight> {
+    let Element::Text(mut text) = element.element else {
         unreachable!()
     };
-    let wrap_mode = text.config().wrap_mode;
+    let mut dimensions = element.dimensions;
+
+    if text.config.wrap_mode == WrapMode::Words && dimensions.width > available_width {
+        let (lines, lines_dimensions) = layout_text_lines(
+            &|text, config| ctx.measure_text(text, config),
+            &text.data,
+            &text.config,
+            available_width,
+        );
+        text.lines = lines;
+        dimensions = lines_dimensions;
+    }
+
+    ElementLayout {
+        _marker: PhantomData,
+        position: element.position,
+        dimensions,
+        element: Element::Text(text),
+        children: Box::new([]),
+    }
+}
 
-    if wrap_mode == WrapMode::None || text_dimensions.width < parent_dim.width {
-        return text;
+/// Splits the text in lines fitting in `max_width` and aligns them in it.
+/// Returns the lines with the size of the whole text, as wide as `max_width`,
+/// or as the widest line when it is infinite.
+pub(crate) fn layout_text_lines(
+    measure_text: &dyn Fn(&str, &TextConfig) -> TextDimensions,
+    text: &str,
+    config: &TextConfig,
+    max_width: f32,
+) -> (Vec<TextLine>, Dimension2D) {
+    let raw_lines = break_lines(measure_text, text, config, max_width);
+
+    // Each line takes the line height, whatever the height of its characters
+    let line_height = config.line_height.resolve(config.font_size);
+
+    let mut lines = raw_lines
+        .iter()
+        .enumerate()
+        .map(|(i, (text, _))| TextLine {
+            text: text.clone(),
+            position: Point2D::new(0.0, i as f32 * line_height),
+            dimensions: Dimension2D::new(measure_text(text, config).width, line_height),
+            word_spacing: 0.0,
+            words: vec![],
+        })
+        .collect::<Vec<_>>();
+
+    let widest = lines
+        .iter()
+        .map(|line| line.dimensions.width)
+        .reduce(f32::max)
+        .unwrap_or_default();
+    let dimensions = Dimension2D::new(
+        if max_width.is_finite() {
+            widest.max(max_width)
+        } else {
+            widest
+        },
+        lines
+            .iter()
+            .map(|line| line.position.y + line.dimensions.height)
+            .reduce(f32::max)
+            .unwrap_or_default(),
+    );
+
+    for (line, (_, ends_paragraph)) in lines.iter_mut().zip(raw_lines) {
+        let free_width = dimensions.width - line.dimensions.width;
+        match config.text_alignment {
+            TextAlignment::Left => {}
+            TextAlignment::Center => line.position.x = free_width / 2.0,
+            TextAlignment::Right => line.position.x = free_width,
+            // The last line of a paragraph is not stretched
+            TextAlignment::Justify => {
+                let nb_spaces = line.text.matches(' ').count();
+                if !ends_paragraph && nb_spaces > 0 {
+                    line.word_spacing = free_width / nb_spaces as f32;
+                    line.words =
+                        justified_words(measure_text, &line.text, config, line.word_spacing);
+                }
+            }
+        }
     }
 
-    let space_len = (ctx.utils.measure_text)(" ", text.config()).width;
+    (lines, dimensions)
+}
+
+/// The words of the line, spread over it by adding `word_spacing` after each
+/// space
+fn justified_words(
+    measure_text: &dyn Fn(&str, &TextConfig) -> TextDimensions,
+    line: &str,
+    config: &TextConfig,
+    word_spacing: f32,
+) -> Vec<TextWord> {
+    // A space is measured with the letter spacing on both of its sides, like
+    // inside of the line
+    let space_width = measure_text(" ", config).width + 2.0 * config.letter_spacing as f32;
+
+    let mut words = vec![];
+    let mut x = 0.0;
+    let mut start = 0;
+    for word in line.split(' ') {
+        let width = measure_text(word, config).width;
+        if !word.is_empty() {
+            words.push(TextWord {
+                range: start..start + word.len(),
+                x,
+                width,
+            });
+        }
+        x += width + space_width + word_spacing;
+        start += word.len() + 1;
+    }
+    words
+}
 
-    let max_width = parent_dim.width;
-    let config = text.config;
-    let id = text.id;
-    let mut data = text.data;
+/// Splits the text in lines with whether they end their paragraph. The lines
+/// are broken where the Unicode line breaking algorithm allows it, and a word
+/// too wide for a line of its own is broken between its characters.
+fn break_lines(
+    measure_text: &dyn Fn(&str, &TextConfig) -> TextDimensions,
+    text: &str,
+    config: &TextConfig,
+    max_width: f32,
+) -> Vec<(String, bool)> {
+    if config.wrap_mode == WrapMode::None {
+        return vec![(text.to_string(), true)];
+    }
 
-    let mut curr_line_len = 0f32;
-    let mut curr_word_start = 0;
+    let width = |text: &str| measure_text(text, config).width;
+    let letter_spacing = config.letter_spacing as f32;
+
+    let mut lines = vec![];
+    let mut line_start = 0;
+    let mut last_break = 0;
+    // The width of the line up to the last break, with its trailing spaces.
+    // Each word is only measured once, and added to it.
+    let mut line_width = 0.0;
+
+    for (idx, opportunity) in unicode_linebreak::linebreaks(text) {
+        if config.wrap_mode == WrapMode::Words {
+            // A word, with the spaces following it
+            let word = &text[last_break..idx];
+            let trimmed = trim_line_end(word);
+            let trimmed_width = width(trimmed);
+
+            if !trimmed.is_empty() {
+                if line_start < last_break
+                    && line_width + letter_spacing + trimmed_width > max_width
+                {
+                    lines.push((
+                        trim_line_end(&text[line_start..last_break]).to_string(),
+                        false,
+                    ));
+                    line_start = last_break;
+                }
 
-    for idx in 0..data.len() {
-        if &data[idx..idx + 1] == " " {
-            let word = &data[curr_word_start..idx];
-            let word_len = (ctx.utils.measure_text)(word, &config);
-            curr_line_len += space_len + word_len.width;
-            if curr_line_len + padding_x > max_width && curr_word_start > 0 {
-                unsafe {
-                    data.as_mut_vec()[curr_word_start - 1] = b'\n';
+                if line_start == last_break && trimmed_width > max_width {
+                    line_start += break_word(&width, trimmed, max_width, &mut lines);
                 }
-                curr_line_len = word_len.width;
             }
 
-            curr_word_start = idx + 1;
+            line_width = if line_start < last_break {
+                line_width + letter_spacing + width(word)
+            } else if line_start == last_break && trimmed.len() == word.len() {
+                trimmed_width
+            } else {
+                width(&text[line_start..idx])
+            };
+        }
+
+        match opportunity {
+            BreakOpportunity::Mandatory => {
+                lines.push((trim_line_end(&text[line_start..idx]).to_string(), true));
+                line_start = idx;
+                line_width = 0.0;
+            }
+            BreakOpportunity::Allowed => {}
         }
+        last_break = idx;
     }
 
-    TextElement::new(config, data, Some(id))
+    lines
+}
+
+/// Pushes the lines of a word too wide for a line of its own, returning where
+/// the rest of the word, which fits in a line, starts. At least one character
+/// is put on each line, even if it is too wide.
+///
+/// The longest start of the rest fitting in a line is searched by doubling the
+/// number of characters, then by bisection, so the starts measured are at most
+/// twice as long as the lines.
+fn break_word(
+    width: &dyn Fn(&str) -> f32,
+    word: &str,
+    max_width: f32,
+    lines: &mut Vec<(String, bool)>,
+) -> usize {
+    // Where each character of the word ends
+    let ends = word
+        .char_indices()
+        .skip(1)
+        .map(|(i, _)| i)
+        .chain([word.len()])
+        .collect::<Vec<_>>();
+    let last = ends.len() - 1;
+
+    let mut start = 0;
+    let mut first = 0;
+    loop {
+        let fits = |end: usize| width(&word[start..ends[end]]) <= max_width;
+
+        // The line ends after the character `fitting`, and before `overflowing`
+        let mut fitting = first;
+        let mut overflowing = None;
+        let mut step = 1;
+        while overflowing.is_none() && fitting < last {
+            let end = (fitting + step).min(last);
+            if fits(end) {
+                fitting = end;
+                step *= 2;
+            } else {
+                overflowing = Some(end);
+            }
+        }
+        let Some(mut overflowing) = overflowing else {
+            return start;
+        };
+
+        while overflowing - fitting > 1 {
+            let end = (fitting + overflowing) / 2;
+            if fits(end) {
+                fitting = end;
+            } else {
+                overflowing = end;
+            }
+        }
+
+        lines.push((word[start..ends[fitting]].to_string(), false));
+        start = ends[fitting];
+        first = fitting + 1;
+    }
+}
+
+/// Removes the spaces and the line break at the end of a line. The non-breaking
+/// spaces are kept, they are part of the text.
+fn trim_line_end(line: &str) -> &str {
+    line.trim_end_matches(|c: char| {
+        c.is_whitespace() && !matches!(c, '\u{A0}' | '\u{2007}' | '\u{202F}')
+    })
 }
 
 pub fn calculate_layout(
     ctx: &AppCtx,
     root: ElementLayout<Initial>,
 ) -> Result<ElementLayout<Done>, RlayError> {
-    root.apply_layout_step(ctx)?
+    let mut layout = root
+        .apply_layout_step(ctx)?
         .apply_layout_step(ctx)?
         .apply_layout_step(ctx)?
         .apply_layout_step(ctx)?
         .apply_layout_step(ctx)?
         .apply_layout_step(ctx)?
-        .apply_layout_step(ctx)
+        .apply_layout_step(ctx)?;
+
+    position_floating_elements(&mut layout);
+
+    Ok(layout)
 }
 
-#[allow(unused)]
-fn resize_text_slow(
-    ctx: &AppCtx,
-    element: &ElementLayout<WrapText>,
-    parent_dim: &Dimension2D,
-) -> String {
-    let Element::Text(text) = element.data() else {
-        unreachable!()
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        AppCtxUtils, ContainerConfig, Dimension2D, FloatingAttachPoint, FloatingAttachPointType,
+        FloatingConfig, FontData, ImageConfig, ImageData, Padding, TextConfig, TextDimensions,
     };
-    let text_dimensions = element.dimensions();
-
-    if text_dimensions.width < parent_dim.width {
-        return text.data().clone();
-    }
-
-    let space_len = (ctx.utils.measure_text)(" ", text.config()).width;
-
-    let max_width = parent_dim.width;
-    let data = text.data().clone();
-    let mut words = data.split(' ');
-    let mut new_data = String::with_capacity(data.len());
-    let mut curr_line_len = 0f32;
-    let mut line = String::new();
-    while let Some(word) = words.next() {
-        let word_len = (ctx.utils.measure_text)(word, text.config());
-        curr_line_len += space_len + word_len.width;
-
-        if curr_line_len > max_width && line.len() > 0 {
-            new_data += &line;
-            new_data.push('\n');
-            line.clear();
-            line.push_str(word);
-            curr_line_len = word_len.width;
-        } else {
-            line.push(' ');
-            line.push_str(word);
+
+    /// The width of a character for [`measure`]
+    const CHAR_WIDTH: f32 = 10.0;
+
+    /// Measures every character with the same width, like a monospace font
+    fn measure(text: &str, config: &TextConfig) -> TextDimensions {
+        let nb_chars = text.chars().count() as f32;
+        TextDimensions {
+            width: nb_chars * CHAR_WIDTH + (nb_chars - 1.0).max(0.0) * config.letter_spacing as f32,
+            height: config.font_size as f32,
+            offset_y: 0.0,
+        }
+    }
+
+    /// Lays out the tree built by `root`, measuring the texts with [`measure`]
+    fn layout_tree(root: impl FnOnce(&mut AppCtx)) -> ElementLayout<Done> {
+        let mut ctx = AppCtx::new(AppCtxUtils::new(
+            |text: &str, config: &TextConfig, _: Option<&FontData>| measure(text, config),
+        ));
+        root(&mut ctx);
+        let root = (&mut ctx).try_into().unwrap();
+        calculate_layout(&ctx, root).unwrap()
+    }
+
+    fn container(ctx: &mut AppCtx, config: ContainerConfig, children: impl FnOnce(&mut AppCtx)) {
+        ctx.open_element(Element::container(config, ctx.get_local_id()));
+        children(ctx);
+        ctx.close_element();
+    }
+
+    fn sized(width: SizingAxis, height: SizingAxis) -> ContainerConfig {
+        ContainerConfig {
+            sizing: Sizing::new(width, height),
+            ..Default::default()
         }
     }
 
-    new_data.push_str(&line);
+    /// A percent sizing without bounds
+    fn percent(part: f32) -> SizingAxis {
+        SizingAxis::Percent(part, MinMax::default())
+    }
+
+    fn children_dimensions(layout: &ElementLayout<Done>) -> Vec<Dimension2D> {
+        layout
+            .children()
+            .iter()
+            .map(|child| child.dimensions())
+            .collect()
+    }
+
+    #[test]
+    fn resolve_percent_against_the_content_box() {
+        let root = ContainerConfig {
+            padding: Padding::new(20, 20, 20, 20),
+            child_gap: 10,
+            ..sized(SizingAxis::Fixed(600.0), SizingAxis::Fixed(400.0))
+        };
+        let layout = layout_tree(|ctx| {
+            container(ctx, root, |ctx| {
+                let half = || sized(percent(0.5), percent(0.25));
+                container(ctx, half(), |_| {});
+                container(ctx, half(), |_| {});
+            })
+        });
+        // The gap between the children is not shared along the direction
+        assert_eq!(
+            children_dimensions(&layout),
+            [Dimension2D::new(275.0, 90.0), Dimension2D::new(275.0, 90.0)]
+        );
+
+        let root = ContainerConfig {
+            padding: Padding::new(10, 10, 10, 10),
+            child_gap: 20,
+            layout_direction: LayoutDirection::TopToBottom,
+            ..sized(SizingAxis::Fixed(300.0), SizingAxis::Fixed(300.0))
+        };
+        let layout = layout_tree(|ctx| {
+            container(ctx, root, |ctx| {
+                let half = || sized(percent(1.0), percent(0.5));
+                container(ctx, half(), |_| {});
+                container(ctx, half(), |_| {});
+            })
+        });
+        assert_eq!(
+            children_dimensions(&layout),
+            [
+                Dimension2D::new(280.0, 130.0),
+                Dimension2D::new(280.0, 130.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn clamp_percent_sizes_to_their_bounds() {
+        let layout = layout_tree(|ctx| {
+            let root = sized(SizingAxis::Fixed(300.0), SizingAxis::Fixed(100.0));
+            container(ctx, root, |ctx| {
+                let at_most = SizingAxis::Percent(0.5, (..=100.0).into());
+                let at_least = SizingAxis::Percent(0.5, (80.0..).into());
+                container(ctx, sized(at_most, at_least), |_| {});
+                let at_least = SizingAxis::Percent(0.25, (100.0..).into());
+                container(ctx, sized(at_least, SizingAxis::Fixed(10.0)), |_| {});
+            })
+        });
+        assert_eq!(
+            children_dimensions(&layout),
+            [Dimension2D::new(100.0, 80.0), Dimension2D::new(100.0, 10.0)]
+        );
+    }
+
+    #[test]
+    fn fit_the_width_of_an_image_growing_in_height() {
+        let image = ImageConfig {
+            sizing: Sizing::new(
+                SizingAxis::Fit(MinMax::default()),
+                SizingAxis::Grow(MinMax::default()),
+            ),
+            src_dimensions: Dimension2D::new(50.0, 25.0),
+        };
+        let layout = layout_tree(|ctx| {
+            let root = ContainerConfig {
+                padding: Padding::new(10, 10, 10, 10),
+                ..sized(SizingAxis::Fit(MinMax::default()), SizingAxis::Fixed(120.0))
+            };
+            container(ctx, root, |ctx| {
+                let data = ImageData::File {
+                    path: "image.png".to_string(),
+                };
+                ctx.open_element(Element::image(image, data, ctx.get_local_id()));
+                ctx.close_element();
+            })
+        });
+        // The image is as high as the root, and keeps its aspect ratio
+        assert_eq!(
+            children_dimensions(&layout),
+            [Dimension2D::new(200.0, 100.0)]
+        );
+        assert_eq!(layout.dimensions(), Dimension2D::new(220.0, 120.0));
+    }
+
+    fn child(width: f32, height: f32, config: ContainerConfig) -> ElementLayout<Initial> {
+        ElementLayout::new(
+            Point2D::default(),
+            Dimension2D::new(width, height),
+            Element::container(config, String::new()),
+            Box::new([]),
+        )
+    }
+
+    #[test]
+    fn wrap_lines_when_the_children_overflow() {
+        let children = [
+            child(30.0, 10.0, Default::default()),
+            child(30.0, 10.0, Default::default()),
+            child(30.0, 10.0, Default::default()),
+        ];
+        assert_eq!(
+            wrap_lines(&children, LayoutDirection::LeftToRight, 70.0, 10.0),
+            [vec![0, 1], vec![2]]
+        );
+
+        let floating = ContainerConfig {
+            floating: Some(FloatingConfig::default()),
+            ..Default::default()
+        };
+        let children = [
+            child(10.0, 60.0, Default::default()),
+            child(10.0, 60.0, floating),
+            child(10.0, 60.0, Default::default()),
+        ];
+        assert_eq!(
+            wrap_lines(&children, LayoutDirection::TopToBottom, 100.0, 0.0),
+            [vec![0], vec![2]]
+        );
+    }
+
+    #[test]
+    fn wrap_lines_with_a_tolerance_for_rounding_errors() {
+        let children = [
+            child(50.004, 10.0, Default::default()),
+            child(50.004, 10.0, Default::default()),
+        ];
+        assert_eq!(
+            wrap_lines(&children, LayoutDirection::LeftToRight, 100.0, 0.0),
+            [vec![0, 1]]
+        );
+
+        let children = [
+            child(50.1, 10.0, Default::default()),
+            child(50.1, 10.0, Default::default()),
+        ];
+        assert_eq!(
+            wrap_lines(&children, LayoutDirection::LeftToRight, 100.0, 0.0),
+            [vec![0], vec![1]]
+        );
+
+        // The thirds don't add up to exactly the width of their parent
+        let root = ContainerConfig {
+            wrap: true,
+            ..sized(SizingAxis::Fixed(100.0), SizingAxis::Fixed(100.0))
+        };
+        let layout = layout_tree(|ctx| {
+            container(ctx, root, |ctx| {
+                for _ in 0..3 {
+                    let third = sized(percent(1.0 / 3.0), SizingAxis::Fixed(10.0));
+                    container(ctx, third, |_| {});
+                }
+            })
+        });
+        for child in layout.children() {
+            assert_eq!(child.position().y, 0.0);
+        }
+    }
+
+    fn spanning(columns: usize, rows: usize) -> ContainerConfig {
+        ContainerConfig {
+            grid_span: GridSpan::new(columns, rows),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn grow_grid_tracks_next_to_percent_tracks() {
+        let grid = GridConfig {
+            columns: vec![
+                GridTrack::Percent(0.25),
+                GridTrack::Grow(1.0),
+                GridTrack::Fixed(40.0),
+                GridTrack::Grow(3.0),
+            ],
+            column_gap: 10,
+            ..Default::default()
+        };
+        let children = [child(0.0, 0.0, Default::default())];
+        let cells = grid_cells(&grid, &children);
+
+        // The grow tracks share what the percent and fixed tracks and the gaps
+        // leave: 400 - 100 - 40 - 3 * 10
+        let sizes = grid_track_sizes(
+            &grid,
+            &cells,
+            &children,
+            LayoutDirection::LeftToRight,
+            Some(400.0),
+        );
+        assert_eq!(sizes, [100.0, 57.5, 40.0, 172.5]);
+
+        // Without the available size, percent tracks are empty and grow tracks
+        // fit their children
+        let sizes = grid_track_sizes(&grid, &cells, &children, LayoutDirection::LeftToRight, None);
+        assert_eq!(sizes, [0.0, 0.0, 40.0, 0.0]);
+    }
 
-    return new_data;
+    #[test]
+    fn fill_grid_cells_with_grow_and_percent_children() {
+        let grid = GridConfig {
+            columns: vec![GridTrack::Percent(0.25), GridTrack::Grow(1.0)],
+            ..Default::default()
+        };
+        let mut children = [
+            child(0.0, 0.0, sized(percent(0.5), SizingAxis::Fixed(10.0))),
+            child(
+                0.0,
+                0.0,
+                sized(SizingAxis::Grow(MinMax::default()), SizingAxis::Fixed(10.0)),
+            ),
+            child(
+                30.0,
+                0.0,
+                sized(SizingAxis::Fixed(30.0), SizingAxis::Fixed(10.0)),
+            ),
+        ];
+        fill_grid_cells(&grid, &mut children, LayoutDirection::LeftToRight, 400.0);
+
+        let widths = children.map(|child| child.dimensions().width);
+        assert_eq!(widths, [50.0, 300.0, 30.0]);
+    }
+
+    #[test]
+    fn clamp_the_spans_past_the_grid() {
+        let grid = GridConfig {
+            columns: vec![GridTrack::Fixed(100.0), GridTrack::Fixed(100.0)],
+            rows: vec![GridTrack::Fixed(50.0)],
+            ..Default::default()
+        };
+        let children = [
+            // Only two columns, and one row past the defined one
+            child(250.0, 120.0, spanning(3, 2)),
+            child(20.0, 30.0, Default::default()),
+            child(20.0, 40.0, Default::default()),
+        ];
+        let cells = grid_cells(&grid, &children);
+        let placed = cells
+            .iter()
+            .map(|cell| {
+                let cell = cell.unwrap();
+                (cell.column, cell.row, cell.span)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            placed,
+            [
+                (0, 0, GridSpan::new(2, 2)),
+                (0, 2, GridSpan::new(1, 1)),
+                (1, 2, GridSpan::new(1, 1))
+            ]
+        );
+
+        let columns = grid_track_sizes(
+            &grid,
+            &cells,
+            &children,
+            LayoutDirection::LeftToRight,
+            Some(200.0),
+        );
+        assert_eq!(columns, [100.0, 100.0]);
+
+        // The rows added fit their children, and the first one gets what the
+        // spanning child is missing
+        let rows = grid_track_sizes(
+            &grid,
+            &cells,
+            &children,
+            LayoutDirection::TopToBottom,
+            Some(500.0),
+        );
+        assert_eq!(rows, [50.0, 70.0, 40.0]);
+    }
+
+    fn text_config(text_alignment: TextAlignment) -> TextConfig {
+        TextConfig {
+            text_alignment,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn align_text_lines() {
+        let config = text_config(TextAlignment::Center);
+        let (lines, dimensions) = layout_text_lines(&measure, "aa bb cccccc", &config, 60.0);
+        assert_eq!(dimensions, Dimension2D::new(60.0, 40.0));
+        assert_eq!(lines[0].text, "aa bb");
+        assert_eq!(lines[0].position, Point2D::new(5.0, 0.0));
+        assert_eq!(lines[1].position, Point2D::new(0.0, 20.0));
+
+        let config = text_config(TextAlignment::Right);
+        let (lines, _) = layout_text_lines(&measure, "aa bb cccccc", &config, 60.0);
+        assert_eq!(lines[0].position, Point2D::new(10.0, 0.0));
+    }
+
+    #[test]
+    fn align_a_single_line_in_a_wider_box() {
+        let config = text_config(TextAlignment::Center);
+        let (lines, dimensions) = layout_text_lines(&measure, "aa", &config, 60.0);
+        assert_eq!(dimensions.width, 60.0);
+        assert_eq!(lines[0].position.x, 20.0);
+
+        let config = text_config(TextAlignment::Right);
+        let (lines, _) = layout_text_lines(&measure, "aa", &config, 60.0);
+        assert_eq!(lines[0].position.x, 40.0);
+
+        // Without a box, the text is as wide as its line
+        let (lines, dimensions) = layout_text_lines(&measure, "aa", &config, f32::INFINITY);
+        assert_eq!(dimensions.width, 20.0);
+        assert_eq!(lines[0].position.x, 0.0);
+
+        // Once wrapped, the lines are aligned in the width of their parent
+        let layout = layout_tree(|ctx| {
+            let root = sized(SizingAxis::Fixed(100.0), SizingAxis::Fit(MinMax::default()));
+            container(ctx, root, |ctx| {
+                let text = Element::text(config, "aaaa bbbbbbbb".to_string(), ctx.get_local_id());
+                ctx.open_element(text);
+                ctx.close_element();
+            })
+        });
+        let Element::Text(text) = layout.children()[0].data() else {
+            unreachable!()
+        };
+        assert_eq!(layout.children()[0].dimensions().width, 100.0);
+        assert_eq!(text.lines[0].position.x, 60.0);
+        assert_eq!(text.lines[1].position.x, 20.0);
+    }
+
+    #[test]
+    fn justify_text_lines_but_the_last_ones() {
+        let config = text_config(TextAlignment::Justify);
+        let (lines, _) = layout_text_lines(&measure, "aa bb cccccc dd", &config, 60.0);
+
+        assert_eq!(lines[0].word_spacing, 10.0);
+        let words = lines[0]
+            .words
+            .iter()
+            .map(|word| (&lines[0].text[word.range.clone()], word.x, word.width))
+            .collect::<Vec<_>>();
+        // The last word ends with the line
+        assert_eq!(words, [("aa", 0.0, 20.0), ("bb", 40.0, 20.0)]);
+
+        // The last line, and the lines without space, are not stretched
+        assert_eq!(lines[1].text, "cccccc");
+        assert_eq!(lines[1].word_spacing, 0.0);
+        assert_eq!(lines[2].text, "dd");
+        assert_eq!(lines[2].word_spacing, 0.0);
+        assert!(lines[2].words.is_empty());
+
+        // Neither are the last lines of the paragraphs
+        let (lines, _) = layout_text_lines(&measure, "a b\ncccccc", &config, 60.0);
+        assert_eq!(lines[0].word_spacing, 0.0);
+        assert!(lines[0].words.is_empty());
+    }
+
+    #[test]
+    fn justify_text_lines_with_letter_spacing() {
+        let config = TextConfig {
+            letter_spacing: 2,
+            ..text_config(TextAlignment::Justify)
+        };
+        // "aa b" is 40 + 3 * 2 wide
+        let (lines, dimensions) = layout_text_lines(&measure, "aa b cccccc", &config, 70.0);
+        assert_eq!(dimensions.width, 70.0);
+        assert_eq!(lines[0].word_spacing, 24.0);
+
+        let last = lines[0].words.last().unwrap();
+        assert_eq!(last.x + last.width, 70.0);
+    }
+
+    fn lines(text: &str, config: &TextConfig, max_width: f32) -> Vec<(String, bool)> {
+        break_lines(&measure, text, config, max_width)
+    }
+
+    fn line(text: &str, ends_paragraph: bool) -> (String, bool) {
+        (text.to_string(), ends_paragraph)
+    }
+
+    #[test]
+    fn break_lines_between_words() {
+        let config = TextConfig::default();
+        assert_eq!(
+            lines("aaa bbb ccc", &config, 75.0),
+            [line("aaa bbb", false), line("ccc", true)]
+        );
+        // The spaces at the end of a line don't need to fit
+        assert_eq!(
+            lines("aaa bbb    ccc", &config, 70.0),
+            [line("aaa bbb", false), line("ccc", true)]
+        );
+    }
+
+    #[test]
+    fn break_lines_at_mandatory_breaks() {
+        let config = TextConfig::default();
+        assert_eq!(
+            lines("aaa\nbbb\r\n\nccc", &config, 1000.0),
+            [
+                line("aaa", true),
+                line("bbb", true),
+                line("", true),
+                line("ccc", true)
+            ]
+        );
+
+        let config = TextConfig {
+            wrap_mode: WrapMode::Newlines,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines("aaa bbb\nccc", &config, 10.0),
+            [line("aaa bbb", true), line("ccc", true)]
+        );
+    }
+
+    #[test]
+    fn break_overlong_words_between_characters() {
+        let config = TextConfig::default();
+        assert_eq!(
+            lines("abcdefghij", &config, 35.0),
+            [
+                line("abc", false),
+                line("def", false),
+                line("ghi", false),
+                line("j", true)
+            ]
+        );
+        assert_eq!(
+            lines("ab abcdefgh cd", &config, 40.0),
+            [
+                line("ab", false),
+                line("abcd", false),
+                line("efgh", false),
+                line("cd", true)
+            ]
+        );
+        // A character wider than the line still gets a line
+        assert_eq!(
+            lines("abc", &config, 5.0),
+            [line("a", false), line("b", false), line("c", true)]
+        );
+    }
+
+    #[test]
+    fn break_lines_on_character_boundaries() {
+        let config = TextConfig::default();
+        assert_eq!(
+            lines("ééééé", &config, 25.0),
+            [line("éé", false), line("éé", false), line("é", true)]
+        );
+        // The ideographs can be broken between each other
+        assert_eq!(
+            lines("日本語", &config, 20.0),
+            [line("日本", false), line("語", true)]
+        );
+    }
+
+    #[test]
+    fn keep_non_breaking_spaces() {
+        let config = TextConfig::default();
+        assert_eq!(
+            lines("aa\u{A0}bb cc", &config, 55.0),
+            [line("aa\u{A0}bb", false), line("cc", true)]
+        );
+        assert_eq!(
+            lines("aa\u{A0} bb", &config, 35.0),
+            [line("aa\u{A0}", false), line("bb", true)]
+        );
+    }
+
+    #[test]
+    fn break_lines_with_letter_spacing() {
+        let config = TextConfig {
+            letter_spacing: 5,
+            ..Default::default()
+        };
+        // 5 characters and 4 letter spacings
+        assert_eq!(lines("aa bb", &config, 70.0), [line("aa bb", true)]);
+        assert_eq!(
+            lines("aa bb", &config, 69.0),
+            [line("aa", false), line("bb", true)]
+        );
+    }
+
+    #[test]
+    fn break_lines_measuring_each_character_a_few_times() {
+        let measured = Cell::new(0);
+        let measure = |text: &str, config: &TextConfig| {
+            measured.set(measured.get() + text.len());
+            measure(text, config)
+        };
+        let config = TextConfig::default();
+
+        let text = "abc ".repeat(250);
+        break_lines(&measure, &text, &config, 10_000.0);
+        assert!(measured.get() <= 5 * text.len());
+
+        measured.set(0);
+        let text = "a".repeat(1000);
+        let lines = break_lines(&measure, &text, &config, 35.0);
+        assert_eq!(lines.len(), 334);
+        assert!(measured.get() <= 5 * text.len());
+    }
+
+    #[test]
+    fn attach_floating_elements_to_their_target_out_of_the_flow() {
+        use FloatingAttachPointType::{CenterBottom, CenterTop, LeftTop, RightBottom, RightTop};
+
+        let floating = |attach_to, element, parent, offset| FloatingConfig {
+            offset,
+            attach_point: FloatingAttachPoint { element, parent },
+            attach_to,
+            ..Default::default()
+        };
+        let open = |ctx: &mut AppCtx, id: &str, width, height, floating| {
+            let config = ContainerConfig {
+                floating,
+                ..sized(SizingAxis::Fixed(width), SizingAxis::Fixed(height))
+            };
+            ctx.open_element(Element::container(config, id.to_owned()));
+        };
+
+        let layout = layout_tree(|ctx| {
+            let root = ContainerConfig {
+                padding: Padding::new(10, 10, 10, 10),
+                ..sized(SizingAxis::Fixed(200.0), SizingAxis::Fixed(200.0))
+            };
+            ctx.open_element(Element::container(root, "root".to_owned()));
+            // Above the anchor, with the badge on its top right corner
+            let anchor = FloatingAttachTo::ElementWithId("anchor".to_owned());
+            let tooltip = floating(anchor, CenterBottom, CenterTop, Point2D::new(0.0, -5.0));
+            open(ctx, "tooltip", 20.0, 10.0, Some(tooltip));
+            let badge = floating(
+                FloatingAttachTo::Parent,
+                LeftTop,
+                RightTop,
+                Point2D::default(),
+            );
+            open(ctx, "badge", 4.0, 4.0, Some(badge));
+            ctx.close_element();
+            ctx.close_element();
+            open(ctx, "anchor", 40.0, 20.0, None);
+            ctx.close_element();
+            let corner = floating(
+                FloatingAttachTo::Root,
+                RightBottom,
+                RightBottom,
+                Point2D::default(),
+            );
+            open(ctx, "corner", 8.0, 8.0, Some(corner));
+            ctx.close_element();
+        });
+        let position = |id| layout.get_element_with_id(id).unwrap().position();
+
+        assert_eq!(position("anchor"), Point2D::new(10.0, 10.0));
+        assert_eq!(position("tooltip"), Point2D::new(20.0, -5.0));
+        assert_eq!(position("badge"), Point2D::new(40.0, -5.0));
+        assert_eq!(position("corner"), Point2D::new(192.0, 192.0));
+    }
 }
@@ -1,7 +1,9 @@
 pub use app_ctx::*;
 pub use elements::*;
 pub use event::*;
+pub use font::*;
 pub use layout::*;
+pub use measure::*;
 pub use render::*;
 pub use state::*;
 
@@ -9,7 +11,9 @@ mod app_ctx;
 pub mod elements;
 pub mod err;
 mod event;
+mod font;
 mod layout;
+mod measure;
 mod mem;
 pub mod reactive;
 mod render;
@@ -45,13 +49,13 @@ macro_rules! rlay {
                 config
             };
 
-            $crate::_attrs!(view, attrs[id]: $($($attrs)*)?);
+            $crate::_attrs!(view, attrs[id, key, index]: $($($attrs)*)?);
 
             $ctx.open_element(
                 $crate::Element::Container(
                     $crate::elements::ContainerElement::new(
                         config,
-                        attrs.get(&"id".to_string()).cloned(),
+                        $crate::_element_id!($ctx, attrs),
                     ))
             );
         }
@@ -70,14 +74,14 @@ macro_rules! rlay {
                 config
             };
 
-            $crate::_attrs!(text, attrs[id]: $($($attrs)*)?);
+            $crate::_attrs!(text, attrs[id, key, index]: $($($attrs)*)?);
 
             $ctx.open_element(
                 $crate::Element::Text(
                     $crate::elements::TextElement::new(
                         text_config,
                         $text.to_string(),
-                        attrs.get(&"id".to_string()).cloned(),
+                        $crate::_element_id!($ctx, attrs),
                     ))
             );
 
@@ -94,14 +98,14 @@ macro_rules! rlay {
                 config
             };
 
-            $crate::_attrs!(image, attrs[id]: $($($attrs:tt)*)?);
+            $crate::_attrs!(image, attrs[id, key, index]: $($($attrs)*)?);
 
             $ctx.open_element(
                 $crate::Element::Image(
                     $crate::elements::ImageElement::new(
                         img_config,
                         $crate::ImageData::File {path: $path.to_string()},
-                        attrs.get(&"id".to_string()).cloned(),
+                        $crate::_element_id!($ctx, attrs),
                     ))
             );
         }
@@ -119,7 +123,7 @@ macro_rules! rlay {
                 config
             };
 
-            $crate::_attrs!(image, attrs[id, file_type]: $($attrs:tt)*);
+            $crate::_attrs!(image, attrs[id, key, index, file_type]: $($attrs)*);
 
             $ctx.open_element(
                 $crate::Element::Image(
@@ -127,9 +131,9 @@ macro_rules! rlay {
                         img_config,
                         $crate::ImageData::Bytes {
                             file_type: attrs.get("file_type").cloned().expect("You must specify the file_type attribute."),
-                            bytes: Box::new($bytes.into()),
+                            bytes: $bytes.into(),
                         },
-                        attrs.get(&"id".to_string()).cloned(),
+                        $crate::_element_id!($ctx, attrs),
                     ))
             );
         }
@@ -157,6 +161,17 @@ macro_rules! _attrs {
     }
 }
 
+#[macro_export]
+macro_rules! _element_id {
+    ($ctx:ident, $attrs:ident) => {
+        $ctx.next_element_id(
+            $attrs.get("id").map(String::as_str),
+            $attrs.get("index").map(String::as_str),
+            $attrs.get("key").map(String::as_str),
+        )
+    };
+}
+
 #[macro_export]
 macro_rules! _attrs2 {
     ([$($allowed_key:ident: $allowed_key_type:ty = $def_val:expr),*]$(<$($gen:ident),+>)?$(where ($($where:tt)*))?: $($attr:ident = $val:expr),* $(,)?) => {{
@@ -208,13 +223,13 @@ macro_rules! view {
                 config
             };
 
-            $crate::_attrs!(view, attrs[id]: $($($attrs)*)?);
+            $crate::_attrs!(view, attrs[id, key, index]: $($($attrs)*)?);
 
             $ctx.open_element(
                 $crate::Element::Container(
                     $crate::elements::ContainerElement::new(
                         config,
-                        attrs.get(&"id".to_string()).cloned(),
+                        $crate::_element_id!($ctx, attrs),
                     ))
             );
         }
@@ -236,14 +251,14 @@ macro_rules! text {
                 config
             };
 
-            $crate::_attrs!(text, attrs[id]: $($($attrs)*)?);
+            $crate::_attrs!(text, attrs[id, key, index]: $($($attrs)*)?);
 
             $ctx.open_element(
                 $crate::Element::Text(
                     $crate::elements::TextElement::new(
                         text_config,
                         $text.to_string(),
-                        attrs.get(&"id".to_string()).cloned(),
+                        $crate::_element_id!($ctx, attrs),
                     ))
             );
 
@@ -293,7 +308,11 @@ macro_rules! _partial_rlay {
 #[macro_export]
 macro_rules! _rlay_field_alias {
     ($config:ident.bg) => {
-        $config.background_color
+        $config.background
+    };
+
+    ($config:ident.background_color) => {
+        $config.background
     };
 
     ($config:ident.$field:ident) => {
@@ -323,6 +342,22 @@ macro_rules! _rlay_field {
         Some($crate::border!($($val)*))
     };
 
+    (shadow = {$($val:tt)*}) => {
+        Some($crate::shadow!($($val)*))
+    };
+
+    (transform = {$($val:tt)*}) => {
+        Some($crate::transform!($($val)*))
+    };
+
+    (floating = {$($val:tt)*}) => {
+        Some($crate::floating!($($val)*))
+    };
+
+    (grid = {$($val:tt)*}) => {
+        Some($crate::grid!($($val)*))
+    };
+
     (corner_radius = {$($val:tt)*}) => {
         Some($crate::corner_radius!($($val)*))
     };
@@ -362,6 +397,22 @@ macro_rules! _partial_rlay_field {
         Some($crate::border!($($val)*))
     };
 
+    (shadow = {$($val:tt)*}) => {
+        Some($crate::shadow!($($val)*))
+    };
+
+    (transform = {$($val:tt)*}) => {
+        Some($crate::transform!($($val)*))
+    };
+
+    (floating = {$($val:tt)*}) => {
+        Some($crate::floating!($($val)*))
+    };
+
+    (grid = {$($val:tt)*}) => {
+        Some($crate::grid!($($val)*))
+    };
+
     (corner_radius = {$($val:tt)*}) => {
         Some($crate::corner_radius!($($val)*))
     };
@@ -393,6 +444,62 @@ macro_rules! border {
     };
 }
 
+#[macro_export]
+macro_rules! shadow {
+    () => {
+        $crate::ShadowConfig::default()
+    };
+
+    ($($field:ident = $val:expr),* $(,)?) => {
+        $crate::ShadowConfig {
+            $($field: $val.into(),)*
+            ..Default::default()
+        }
+    };
+}
+
+/// Fields can be given like in `rlay!`, e.g. `angle = {45 deg}`
+#[macro_export]
+macro_rules! transform {
+    () => {
+        $crate::Transform::default()
+    };
+
+    ($($fields:tt)+) => {{
+        let mut transform = $crate::Transform::default();
+        $crate::_rlay!(transform; $($fields)+);
+        transform
+    }};
+}
+
+#[macro_export]
+macro_rules! floating {
+    () => {
+        $crate::FloatingConfig::default()
+    };
+
+    ($($field:ident = $val:expr),* $(,)?) => {
+        $crate::FloatingConfig {
+            $($field: $val.into(),)*
+            ..Default::default()
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! grid {
+    () => {
+        $crate::GridConfig::default()
+    };
+
+    ($($field:ident = $val:expr),* $(,)?) => {
+        $crate::GridConfig {
+            $($field: $val.into(),)*
+            ..Default::default()
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! corner_radius {
     () => {
@@ -472,7 +579,7 @@ macro_rules! _sizing_axis {
         {
             let val = $val as f32;
             assert!((0.0..=1.0).contains(&val), "Percent value must be between 0 and 1, got {}", val);
-            $crate::SizingAxis::Percent(val)
+            $crate::SizingAxis::Percent(val, $crate::MinMax::default())
         }
     };
 
@@ -480,7 +587,7 @@ macro_rules! _sizing_axis {
         {
             let val = $val as f32 / 100.0;
             assert!((0.0..=1.0).contains(&val), "Percent value must be between 0 and 1, got {}", val);
-            $crate::SizingAxis::Percent(val)
+            $crate::SizingAxis::Percent(val, $crate::MinMax::default())
         }
     };
 }
@@ -1,19 +1,31 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
 use macroquad::{
-    color::{BLACK, Color},
+    camera::{Camera2D, set_camera, set_default_camera},
+    color::{BLACK, BLANK, Color},
     input::{
-        self, KeyCode, get_char_pressed, get_keys_pressed, get_last_key_pressed, is_key_down,
-        is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, is_mouse_button_released,
-        mouse_delta_position, mouse_position,
+        self, KeyCode, get_char_pressed, get_keys_down, get_keys_pressed, get_keys_released,
+        is_key_down, is_mouse_button_down, is_mouse_button_pressed, is_mouse_button_released,
+        mouse_delta_position, mouse_position, mouse_wheel, utils,
+    },
+    math::{Mat4, Rect, vec2, vec3},
+    miniquad::{EventHandler, KeyMods},
+    models::{Mesh, Vertex, draw_mesh},
+    shapes::{draw_circle, draw_line, draw_rectangle, draw_triangle},
+    text::{Font, TextParams, draw_text_ex, load_ttf_font_from_bytes, measure_text},
+    texture::{DrawTextureParams, Image, RenderTarget, Texture2D, draw_texture_ex, render_target},
+    time::get_time,
+    window::{
+        clear_background, get_internal_gl, next_frame, screen_dpi_scale, screen_height,
+        screen_width,
     },
-    shapes::{draw_circle, draw_rectangle},
-    text::{TextParams, draw_multiline_text_ex, measure_text},
-    window::{clear_background, next_frame, screen_height, screen_width},
 };
 
 use crate::{
-    AppCtx, AppCtxUtils, Color as RlayColor, ContainerConfig, ContainerElement, Element,
-    InputState, KeyboardInput, MouseButtonState, MouseInput, RlayKeyboardKey, RootFactory,
-    TextConfig, TextDimensions,
+    AppCtx, AppCtxUtils, Color as RlayColor, ContainerConfig, ContainerElement, Element, FontCache,
+    FontData, FontSource, ImageData, InputState, KeyboardInput, MouseButtonState, MouseInput,
+    RlayKeyboardKey, RootFactory, ScissorStack, TextConfig, TextDimensions, TextureCache,
+    Transform,
     commands::RlayDrawCommand,
     err::RlayError,
     layout::{Dimension2D, Point2D},
@@ -143,16 +155,149 @@ impl From<RlayKeyboardKey> for macroquad::input::KeyCode {
             RlayKeyboardKey::KEY_KP_EQUAL => Self::KpEqual,
             RlayKeyboardKey::KEY_BACK => Self::Back,
             RlayKeyboardKey::KEY_MENU => Self::Menu,
-            RlayKeyboardKey::KEY_NULL => todo!(),
-            RlayKeyboardKey::KEY_VOLUME_UP => todo!(),
-            RlayKeyboardKey::KEY_VOLUME_DOWN => todo!(),
+            RlayKeyboardKey::KEY_NULL
+            | RlayKeyboardKey::KEY_VOLUME_UP
+            | RlayKeyboardKey::KEY_VOLUME_DOWN => Self::Unknown,
+        }
+    }
+}
+
+fn rlay_key(code: KeyCode) -> Option<RlayKeyboardKey> {
+    if code == KeyCode::Unknown {
+        return None;
+    }
+    RlayKeyboardKey::ALL
+        .into_iter()
+        .find(|key| KeyCode::from(*key) == code)
+}
+
+fn rlay_keys(codes: HashSet<KeyCode>) -> HashSet<RlayKeyboardKey> {
+    codes.into_iter().filter_map(rlay_key).collect()
+}
+
+/// Collects the repeated keys, which macroquad only gives with its input
+/// events
+#[derive(Default)]
+struct KeyRepeats(HashSet<RlayKeyboardKey>);
+
+impl EventHandler for KeyRepeats {
+    fn update(&mut self) {}
+
+    fn draw(&mut self) {}
+
+    fn key_down_event(&mut self, keycode: KeyCode, _keymods: KeyMods, repeat: bool) {
+        if repeat && let Some(key) = rlay_key(keycode) {
+            self.0.insert(key);
         }
     }
 }
 
 #[derive(Default)]
 pub struct MacroquadRenderer {
-    current_angle: f32,
+    textures: TextureCache<Texture2D>,
+    /// The render targets of the layers, by depth, reused from frame to frame
+    layers: Vec<RenderTarget>,
+    /// Shared with the function measuring the texts
+    fonts: Rc<RefCell<FontCache<Font>>>,
+    /// Receives the input events, registered on the first frame
+    input_subscriber: Option<usize>,
+}
+
+fn load_texture(data: &ImageData) -> Option<Texture2D> {
+    let image = match data {
+        ImageData::File { path } => Image::from_file_with_format(&std::fs::read(path).ok()?, None),
+        ImageData::Bytes { bytes, .. } => Image::from_file_with_format(bytes, None),
+    };
+    image.ok().map(|image| Texture2D::from_image(&image))
+}
+
+fn load_font(source: &FontSource) -> Option<Font> {
+    match source {
+        FontSource::File { path } => load_ttf_font_from_bytes(&std::fs::read(path).ok()?).ok(),
+        FontSource::Bytes { bytes } => load_ttf_font_from_bytes(bytes).ok(),
+    }
+}
+
+/// The font to draw the text with. `None` is the default font of macroquad,
+/// used as well when the font can't be loaded.
+fn text_font<'a>(fonts: &'a mut FontCache<Font>, font: Option<&FontData>) -> Option<&'a Font> {
+    let font = font?;
+    fonts.get_or_load(&font.id, |_| load_font(&font.source))
+}
+
+/// Draws the text, adding `spacing` between its characters. Macroquad can't
+/// space the characters, so they are drawn one by one when there is a spacing.
+fn draw_text_spaced(text: &str, x: f32, y: f32, params: TextParams, spacing: f32) {
+    if spacing == 0.0 {
+        draw_text_ex(text, x, y, params);
+        return;
+    }
+
+    let mut x = x;
+    for (i, c) in text.char_indices() {
+        let dimensions = draw_text_ex(&text[i..i + c.len_utf8()], x, y, params.clone());
+        x += dimensions.width + spacing;
+    }
+}
+
+fn draw_triangles(vertices: &[Point2D], color: RlayColor) {
+    for triangle in vertices.chunks_exact(3) {
+        draw_triangle(
+            vec2(triangle[0].x, triangle[0].y),
+            vec2(triangle[1].x, triangle[1].y),
+            vec2(triangle[2].x, triangle[2].y),
+            color.into(),
+        );
+    }
+}
+
+/// Draws the triangles with a color for each vertex, in meshes small enough to
+/// be indexed with `u16`
+fn draw_colored_triangles(vertices: &[(Point2D, RlayColor)]) {
+    for chunk in vertices.chunks(3 * 10_000) {
+        let mesh = Mesh {
+            vertices: chunk
+                .iter()
+                .map(|(point, color)| Vertex::new(point.x, point.y, 0.0, 0.0, 0.0, (*color).into()))
+                .collect(),
+            indices: (0..chunk.len() as u16).collect(),
+            texture: None,
+        };
+        draw_mesh(&mesh);
+    }
+}
+
+fn transform_matrix(pivot: Point2D, transform: Transform) -> Mat4 {
+    Mat4::from_translation(vec3(
+        pivot.x + transform.translation.x,
+        pivot.y + transform.translation.y,
+        0.0,
+    )) * Mat4::from_rotation_z(transform.angle)
+        * Mat4::from_scale(vec3(transform.scale.x, transform.scale.y, 1.0))
+        * Mat4::from_translation(vec3(-pivot.x, -pivot.y, 0.0))
+}
+
+/// Draws on the render target, with the coordinates of the screen
+fn layer_camera(target: &RenderTarget) -> Camera2D {
+    let mut camera =
+        Camera2D::from_display_rect(Rect::new(0.0, 0.0, screen_width(), screen_height()));
+    camera.render_target = Some(target.clone());
+    camera
+}
+
+/// Restricts drawing to the area, or removes the restriction
+fn set_scissor(area: Option<(Point2D, Dimension2D)>) {
+    // The scissor is in pixels of the framebuffer
+    let scale = screen_dpi_scale();
+    let clip = area.map(|(position, dimensions)| {
+        (
+            (position.x * scale) as i32,
+            (position.y * scale) as i32,
+            (dimensions.width * scale) as i32,
+            (dimensions.height * scale) as i32,
+        )
+    });
+    unsafe { get_internal_gl() }.quad_gl.scissor(clip);
 }
 
 impl RenderImpl for MacroquadRenderer {
@@ -160,21 +305,24 @@ impl RenderImpl for MacroquadRenderer {
     where
         R: RootFactory,
     {
-        let fns = AppCtxUtils {
-            measure_text: Box::new(|text: &str, config: &TextConfig| -> TextDimensions {
-                let text_dim = measure_text(text, None, config.font_size, 1.0);
+        let mut renderer_impl = MacroquadRenderer::default();
+        let fonts = renderer_impl.fonts.clone();
+        let fns = AppCtxUtils::new(
+            move |text: &str, config: &TextConfig, font: Option<&FontData>| {
+                let mut fonts = fonts.borrow_mut();
+                let font = text_font(&mut fonts, font);
+                let text_dim = measure_text(text, font, config.font_size, 1.0);
+                let nb_spacings = text.chars().count().max(1) - 1;
                 TextDimensions {
-                    width: text_dim.width,
+                    width: text_dim.width + (nb_spacings * config.letter_spacing as usize) as f32,
                     height: text_dim.height,
                     offset_y: text_dim.offset_y,
                 }
-            }),
-            is_key_pressed: Box::new(|key| is_key_pressed(key.into())),
-        };
+            },
+        );
 
         let mut ctx = AppCtx::new(fns);
         loop {
-            let mut renderer_impl = MacroquadRenderer::default();
             let (new_ctx, draws) =
                 renderer::process_frame(&mut renderer_impl, ctx, root_factory.clone())
                     .expect("error when rendering frame");
@@ -182,6 +330,15 @@ impl RenderImpl for MacroquadRenderer {
             ctx = new_ctx;
 
             clear_background(BLACK);
+            let mut scissors = ScissorStack::default();
+
+            // The layers cover the screen, they are made again when it is resized
+            let layer_size = (vec2(screen_width(), screen_height()) * screen_dpi_scale()).floor();
+            renderer_impl
+                .layers
+                .retain(|layer| layer.texture.size() == layer_size);
+            let mut depth = 0;
+            let mut transforms = vec![];
             for draw in draws {
                 match draw {
                     RlayDrawCommand::DrawRectangle {
@@ -202,31 +359,133 @@ impl RenderImpl for MacroquadRenderer {
                         let Point2D { x, y } = position;
                         draw_circle(x, y, radius, color.into());
                     }
+                    RlayDrawCommand::DrawLine {
+                        start,
+                        end,
+                        thickness,
+                        color,
+                    } => {
+                        draw_line(start.x, start.y, end.x, end.y, thickness, color.into());
+                    }
+                    RlayDrawCommand::DrawTriangles { vertices, color } => {
+                        draw_triangles(&vertices, color);
+                    }
+                    command @ RlayDrawCommand::DrawGradient { .. } => {
+                        if let Some(vertices) = command.colored_triangles() {
+                            draw_colored_triangles(&vertices);
+                        }
+                    }
+                    // Macroquad has no rounded shapes or paths, they are
+                    // drawn as triangles
+                    command @ (RlayDrawCommand::DrawRoundedRect { .. }
+                    | RlayDrawCommand::DrawBorder { .. }
+                    | RlayDrawCommand::DrawPath { .. }) => {
+                        if let Some((vertices, color)) = command.triangles() {
+                            draw_triangles(&vertices, color);
+                        }
+                    }
                     RlayDrawCommand::DrawText {
                         text,
                         position,
                         config,
+                        font,
                         ..
                     } => {
                         let Point2D { x, y } = position;
+                        let mut fonts = renderer_impl.fonts.borrow_mut();
 
-                        draw_multiline_text_ex(
+                        draw_text_spaced(
                             &text,
                             x,
                             y,
-                            None,
                             TextParams {
+                                font: text_font(&mut fonts, font.as_ref()),
                                 font_size: config.font_size,
                                 color: config.color.into(),
                                 ..Default::default()
                             },
+                            config.letter_spacing as f32,
                         );
                     }
                     RlayDrawCommand::DrawImage {
                         data,
                         position,
                         dimensions,
-                    } => todo!(),
+                        opacity,
+                    } => {
+                        let Some(texture) = renderer_impl.textures.get_or_load(&data, load_texture)
+                        else {
+                            continue;
+                        };
+                        let Point2D { x, y } = position;
+                        let Dimension2D { width, height } = dimensions;
+
+                        draw_texture_ex(
+                            texture,
+                            x,
+                            y,
+                            Color::new(1.0, 1.0, 1.0, opacity),
+                            DrawTextureParams {
+                                dest_size: Some(vec2(width, height)),
+                                ..Default::default()
+                            },
+                        );
+                    }
+                    RlayDrawCommand::BeginScissor {
+                        position,
+                        dimensions,
+                    } => {
+                        set_scissor(Some(scissors.push(position, dimensions)));
+                    }
+                    RlayDrawCommand::EndScissor => {
+                        set_scissor(scissors.pop());
+                    }
+                    RlayDrawCommand::BeginTransform { pivot, transform } => {
+                        let matrix = transform_matrix(pivot, transform);
+                        transforms.push(matrix);
+                        unsafe { get_internal_gl() }
+                            .quad_gl
+                            .push_model_matrix(matrix);
+                    }
+                    RlayDrawCommand::EndTransform => {
+                        transforms.pop();
+                        unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+                    }
+                    RlayDrawCommand::BeginLayer => {
+                        if renderer_impl.layers.len() == depth {
+                            renderer_impl
+                                .layers
+                                .push(render_target(layer_size.x as u32, layer_size.y as u32));
+                        }
+                        set_camera(&layer_camera(&renderer_impl.layers[depth]));
+                        clear_background(BLANK);
+                        depth += 1;
+                    }
+                    RlayDrawCommand::EndLayer { opacity } => {
+                        depth -= 1;
+                        match depth {
+                            0 => set_default_camera(),
+                            _ => set_camera(&layer_camera(&renderer_impl.layers[depth - 1])),
+                        }
+                        // What is in the layer is already transformed, the
+                        // transforms are undone while it is drawn
+                        let transformed = transforms.iter().fold(Mat4::IDENTITY, |a, b| a * *b);
+                        unsafe { get_internal_gl() }
+                            .quad_gl
+                            .push_model_matrix(transformed.inverse());
+                        draw_texture_ex(
+                            &renderer_impl.layers[depth].texture,
+                            0.0,
+                            0.0,
+                            Color::new(1.0, 1.0, 1.0, opacity),
+                            DrawTextureParams {
+                                dest_size: Some(vec2(screen_width(), screen_height())),
+                                flip_y: true,
+                                ..Default::default()
+                            },
+                        );
+                        unsafe { get_internal_gl() }.quad_gl.pop_model_matrix();
+                    }
                 }
             }
 
@@ -240,13 +499,23 @@ impl RenderImpl for MacroquadRenderer {
                 sizing: sizing!(Fixed(screen_width()), Fixed(screen_height())),
                 ..Default::default()
             },
-            None,
+            ctx.next_element_id(None, None, None),
         ));
 
         ctx.open_element(screen_root);
     }
 
     fn next_input_state(&mut self, ctx: &mut AppCtx) -> InputState {
+        let subscriber = *self
+            .input_subscriber
+            .get_or_insert_with(utils::register_input_subscriber);
+        let mut repeats = KeyRepeats::default();
+        utils::repeat_all_miniquad_input(&mut repeats, subscriber);
+
+        // The queue of macroquad gives the last character first
+        let mut chars_pressed: Vec<_> = std::iter::from_fn(get_char_pressed).collect();
+        chars_pressed.reverse();
+
         InputState {
             mouse: MouseInput {
                 mouse_position: mouse_position().into(),
@@ -284,27 +553,20 @@ impl RenderImpl for MacroquadRenderer {
                         MouseButtonState::Up
                     }
                 },
+                mouse_wheel: mouse_wheel().into(),
             },
             keyboard: KeyboardInput {
-                // keys_down: get_keys_down()
-                //     .into_iter()
-                //     .map(|code| code as u16)
-                //     .collect(),
-                // keys_released: get_keys_released()
-                //     .into_iter()
-                //     .map(|code| code as u16)
-                //     .collect(),
-                keys_pressed: get_keys_pressed()
-                    .into_iter()
-                    .map(|code| code as u16)
-                    .collect(),
-                last_key_pressed: get_last_key_pressed().map(|k| k as u16),
-                last_char_pressed: get_char_pressed(),
+                keys_down: rlay_keys(get_keys_down()),
+                keys_pressed: rlay_keys(get_keys_pressed()),
+                keys_released: rlay_keys(get_keys_released()),
+                keys_repeated: repeats.0,
+                chars_pressed,
                 shift_down: is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift),
                 ctrl_down: is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl),
                 alt_down: is_key_down(KeyCode::LeftAlt) || is_key_down(KeyCode::RightAlt),
                 super_down: is_key_down(KeyCode::LeftSuper) || is_key_down(KeyCode::RightSuper),
             },
+            time: get_time(),
         }
     }
 }
@@ -0,0 +1,243 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use fontdue::{Font, FontSettings};
+
+use crate::{
+    AppCtxUtils, FontCache, FontData, FontSource, TextConfig, TextDimensions, err::RlayError,
+};
+
+/// Measures the texts with fontdue, so the layout can be computed without a
+/// window, e.g. in tests or on a server, with the same numbers everywhere.
+pub struct FontdueMeasurer {
+    default_font: Font,
+    fonts: RefCell<FontCache<Font>>,
+    /// The advance of the glyphs, by font id, character and font size
+    advances: RefCell<HashMap<(u16, char, u16), f32>>,
+}
+
+fn load_font(bytes: &[u8]) -> Result<Font, RlayError> {
+    Font::from_bytes(bytes, FontSettings::default())
+        .map_err(|err| RlayError::InvalidFont(err.to_string()))
+}
+
+fn load_source(source: &FontSource) -> Option<Font> {
+    match source {
+        FontSource::File { path } => load_font(&std::fs::read(path).ok()?).ok(),
+        FontSource::Bytes { bytes } => load_font(bytes).ok(),
+    }
+}
+
+impl FontdueMeasurer {
+    /// `default_font` is the content of the font file used by the texts
+    /// without a font, or whose font can't be loaded
+    pub fn new(default_font: &[u8]) -> Result<Self, RlayError> {
+        Ok(Self {
+            default_font: load_font(default_font)?,
+            fonts: RefCell::default(),
+            advances: RefCell::default(),
+        })
+    }
+
+    pub fn measure_text(
+        &self,
+        text: &str,
+        config: &TextConfig,
+        font: Option<&FontData>,
+    ) -> TextDimensions {
+        let mut fonts = self.fonts.borrow_mut();
+        let (font_id, font) = font
+            .and_then(|font| {
+                let loaded = fonts.get_or_load(&font.id, |_| load_source(&font.source))?;
+                Some((font.id, loaded))
+            })
+            .unwrap_or((0, &self.default_font));
+
+        let font_size = config.font_size as f32;
+        let mut advances = self.advances.borrow_mut();
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            width += *advances
+                .entry((font_id, c, config.font_size))
+                .or_insert_with(|| font.metrics(c, font_size).advance_width);
+
+            if let Some(previous) = previous {
+                width += font.horizontal_kern(previous, c, font_size).unwrap_or(0.0)
+                    + config.letter_spacing as f32;
+            }
+            previous = Some(c);
+        }
+
+        let height = font
+            .horizontal_line_metrics(font_size)
+            .map_or(font_size, |metrics| metrics.ascent - metrics.descent);
+
+        TextDimensions {
+            width,
+            height,
+            offset_y: 0.0,
+        }
+    }
+}
+
+/// Utils measuring the texts with fontdue
+impl From<FontdueMeasurer> for AppCtxUtils {
+    fn from(measurer: FontdueMeasurer) -> Self {
+        Self::new(
+            move |text: &str, config: &TextConfig, font: Option<&FontData>| {
+                measurer.measure_text(text, config, font)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AppCtx, ContainerConfig, Dimension2D, Element, Padding, Sizing, SizingAxis,
+        calculate_layout,
+    };
+
+    const ROBOTO: &[u8] = include_bytes!("../../src/examples/assets/Roboto-VariableFont.ttf");
+
+    fn measurer() -> FontdueMeasurer {
+        FontdueMeasurer::new(ROBOTO).unwrap()
+    }
+
+    fn width(measurer: &FontdueMeasurer, text: &str, config: &TextConfig) -> f32 {
+        measurer.measure_text(text, config, None).width
+    }
+
+    #[test]
+    fn measure_the_width_of_texts() {
+        let measurer = measurer();
+        let config = TextConfig::default();
+
+        assert_eq!(width(&measurer, "", &config), 0.0);
+        let width_a = width(&measurer, "a", &config);
+        assert!(width_a > 0.0);
+        assert!(width(&measurer, "i", &config) < width(&measurer, "m", &config));
+        assert!(width(&measurer, "ab", &config) > width_a);
+
+        // The advances are proportional to the font size
+        let double = TextConfig {
+            font_size: config.font_size * 2,
+            ..Default::default()
+        };
+        let ratio =
+            width(&measurer, "Hello world", &double) / width(&measurer, "Hello world", &config);
+        assert!((ratio - 2.0).abs() < 0.01);
+
+        let dimensions = measurer.measure_text("Hello", &config, None);
+        assert!(dimensions.height > 0.0);
+    }
+
+    #[test]
+    fn add_the_letter_spacing_between_the_characters() {
+        let measurer = measurer();
+        let config = TextConfig::default();
+        let spaced = TextConfig {
+            letter_spacing: 5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            width(&measurer, "a", &spaced),
+            width(&measurer, "a", &config)
+        );
+        assert_eq!(
+            width(&measurer, "abc", &spaced),
+            width(&measurer, "abc", &config) + 2.0 * 5.0
+        );
+        assert_eq!(
+            width(&measurer, "éàc", &spaced),
+            width(&measurer, "éàc", &config) + 2.0 * 5.0
+        );
+    }
+
+    #[test]
+    fn fall_back_to_the_default_font() {
+        let measurer = measurer();
+        let config = TextConfig::default();
+        let default = width(&measurer, "Hello", &config);
+
+        let missing = FontData {
+            id: 1,
+            name: "Missing".to_string(),
+            source: FontSource::File {
+                path: "missing.ttf".to_string(),
+            },
+        };
+        let roboto = FontData {
+            id: 2,
+            name: "Roboto".to_string(),
+            source: FontSource::Bytes {
+                bytes: ROBOTO.into(),
+            },
+        };
+        assert_eq!(
+            measurer
+                .measure_text("Hello", &config, Some(&missing))
+                .width,
+            default
+        );
+        assert_eq!(
+            measurer.measure_text("Hello", &config, Some(&roboto)).width,
+            default
+        );
+    }
+
+    #[test]
+    fn lay_out_texts_measured_with_fontdue() {
+        let mut ctx = AppCtx::new(measurer().into());
+        let config = TextConfig::default();
+        let text_width = ctx.measure_text("Hello world", &config).width;
+        let hello_width = ctx.measure_text("Hello", &config).width;
+
+        let root = ContainerConfig {
+            padding: Padding::new(10, 10, 10, 10),
+            child_gap: 5,
+            ..Default::default()
+        };
+        ctx.open_element(Element::container(root, "root".to_string()));
+        ctx.open_element(Element::text(
+            config.clone(),
+            "Hello world".to_string(),
+            "fit".to_string(),
+        ));
+        ctx.close_element();
+        // Only one of the words fits on a line
+        let narrow = ContainerConfig {
+            sizing: Sizing::new(SizingAxis::Fixed(hello_width + 1.0), SizingAxis::default()),
+            ..Default::default()
+        };
+        ctx.open_element(Element::container(narrow, "narrow".to_string()));
+        ctx.open_element(Element::text(
+            config.clone(),
+            "Hello Hello".to_string(),
+            "wrapped".to_string(),
+        ));
+        ctx.close_element();
+        ctx.close_element();
+
+        let root = (&mut ctx).try_into().unwrap();
+        let layout = calculate_layout(&ctx, root).unwrap();
+        let dimensions = |id: &str| layout.get_element_with_id(id).unwrap().dimensions();
+
+        let line_height = config.line_height.resolve(config.font_size);
+        assert_eq!(dimensions("fit"), Dimension2D::new(text_width, line_height));
+        // The wrapped text takes the width it is wrapped in
+        assert_eq!(
+            dimensions("wrapped"),
+            Dimension2D::new(hello_width + 1.0, 2.0 * line_height)
+        );
+        assert_eq!(
+            dimensions("root"),
+            Dimension2D::new(
+                10.0 + text_width + 5.0 + hello_width + 1.0 + 10.0,
+                10.0 + 2.0 * line_height + 10.0
+            )
+        );
+    }
+}
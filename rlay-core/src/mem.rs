@@ -12,108 +12,102 @@ pub enum MemError {
     ParentAlreadyDefined(usize),
 }
 
-pub struct ArenaTree<T>
-where
-    T: PartialEq,
-{
+/// A tree stored in a single `Vec`, where nodes refer to each other by index.
+///
+/// Clearing the tree keeps its nodes around, so the next nodes inserted reuse
+/// them instead of allocating again.
+pub struct ArenaTree<T> {
     arena: Vec<Node<T>>,
+    /// The number of nodes in use, the ones after it are kept to be reused
+    len: usize,
 }
 
-impl<T: PartialEq> Default for ArenaTree<T> {
+impl<T> Default for ArenaTree<T> {
     fn default() -> Self {
         Self {
             arena: Default::default(),
+            len: 0,
         }
     }
 }
 
-impl<T> ArenaTree<T>
-where
-    T: PartialEq,
-{
-    fn get_next_idx(&mut self, val: &T) -> usize {
-        for node in &self.arena {
-            if &node.val == val {
-                return node.idx;
-            }
-        }
-        self.arena.len()
+impl<T> ArenaTree<T> {
+    fn node_exists(&self, idx: usize) -> bool {
+        idx < self.len
     }
 
-    fn node_exists(&self, idx: usize) -> bool {
-        self.arena.get(idx).is_some()
+    fn get_node(&self, idx: usize) -> Option<&Node<T>> {
+        self.arena[..self.len].get(idx)
     }
 
-    // fn get_mut(&mut self, idx: usize) -> Option<&mut Node<T>> {
-    //     self.arena.get_mut(idx)
-    // }
+    fn nodes(&self) -> &[Node<T>] {
+        &self.arena[..self.len]
+    }
 }
 
-impl<T> ArenaTree<T>
-where
-    T: PartialEq,
-{
+impl<T> ArenaTree<T> {
     pub fn new() -> Self {
-        Self { arena: vec![] }
+        Self::default()
     }
 
     pub fn clear(&mut self) {
-        self.arena.clear();
+        self.len = 0;
     }
 
     pub fn len(&self) -> usize {
-        self.arena.len()
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
     pub fn get_val(&self, idx: usize) -> Option<&T> {
-        self.arena.get(idx).map(Node::val)
+        self.get_node(idx).and_then(Node::val)
+    }
+
+    /// Takes the value out of the node, leaving it empty until the tree is cleared
+    pub fn take_val(&mut self, idx: usize) -> Option<T> {
+        if !self.node_exists(idx) {
+            return None;
+        }
+        self.arena[idx].val.take()
     }
 
     pub fn get_children_val(&self, parent_idx: usize) -> Option<Box<[&T]>> {
         Some(
-            self.arena
-                .get(parent_idx)?
+            self.get_node(parent_idx)?
                 .children
                 .iter()
-                .map(|child| self.get_val(*child).expect("Valid child index"))
+                .filter_map(|child| self.get_val(*child))
                 .collect(),
         )
     }
 
-    pub fn get_nb_children(&self, parent_idx: usize) -> Option<usize> {
-        Some(self.arena.get(parent_idx)?.children.len())
+    pub fn get_children(&self, parent_idx: usize) -> Option<&[usize]> {
+        Some(&self.get_node(parent_idx)?.children)
     }
 
-    pub fn find(&self, val: &T) -> Option<usize> {
-        for node in &self.arena {
-            if &node.val == val {
-                return Some(node.idx);
-            }
-        }
-        None
-    }
-
-    pub fn set(&self, val: T) -> Option<usize> {
-        for node in &self.arena {
-            if node.val == val {
-                return Some(node.idx);
-            }
-        }
-        None
+    pub fn get_nb_children(&self, parent_idx: usize) -> Option<usize> {
+        Some(self.get_node(parent_idx)?.children.len())
     }
 
     pub fn insert_node(&mut self, val: T, parent_idx: Option<usize>) -> Result<usize, MemError> {
         // If we provide a parent, make sure it exists
-        if let Some(parent_idx) = parent_idx {
-            if !self.node_exists(parent_idx) {
-                return Err(MemError::UndefinedNode(parent_idx));
-            }
+        if let Some(parent_idx) = parent_idx
+            && !self.node_exists(parent_idx)
+        {
+            return Err(MemError::UndefinedNode(parent_idx));
         }
 
-        let node_idx = self.get_next_idx(&val);
+        let node_idx = self.len;
 
-        self.arena
-            .push(Node::new_with_parent(node_idx, val, parent_idx));
+        if let Some(node) = self.arena.get_mut(node_idx) {
+            node.reset(val, parent_idx);
+        } else {
+            self.arena.push(Node::new_with_parent(val, parent_idx));
+        }
+        self.len += 1;
 
         if let Some(parent_idx) = parent_idx {
             self.arena[parent_idx].children.push(node_idx);
@@ -146,40 +140,30 @@ where
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Node<T>
-where
-    T: PartialEq,
-{
-    idx: usize,
-    val: T,
+pub struct Node<T> {
+    val: Option<T>,
     parent: Option<usize>,
     children: Vec<usize>,
 }
 
-impl<T> Node<T>
-where
-    T: PartialEq,
-{
-    // pub fn new(idx: usize, val: T) -> Self {
-    //     Self {
-    //         idx,
-    //         val,
-    //         parent: None,
-    //         children: vec![],
-    //     }
-    // }
-
-    fn new_with_parent(idx: usize, val: T, parent: Option<usize>) -> Self {
+impl<T> Node<T> {
+    fn new_with_parent(val: T, parent: Option<usize>) -> Self {
         Self {
-            idx,
-            val,
+            val: Some(val),
             parent,
             children: vec![],
         }
     }
 
-    pub fn val(&self) -> &T {
-        &self.val
+    /// Reuses the node for a new value, keeping the allocation of its children
+    fn reset(&mut self, val: T, parent: Option<usize>) {
+        self.val = Some(val);
+        self.parent = parent;
+        self.children.clear();
+    }
+
+    pub fn val(&self) -> Option<&T> {
+        self.val.as_ref()
     }
 }
 
@@ -188,11 +172,9 @@ pub type ArenaElement = ArenaTree<Element>;
 
 impl ArenaElement {
     pub fn get_element_with_id(&self, id: &str) -> Option<&Element> {
-        for el in self.arena.iter() {
-            if el.val.id() == id {
-                return Some(&el.val);
-            }
-        }
-        None
+        self.nodes()
+            .iter()
+            .filter_map(Node::val)
+            .find(|el| el.id() == id)
     }
 }
@@ -1,9 +1,12 @@
+use std::{cell::RefCell, collections::HashSet, ffi::CString, rc::Rc};
+
 use raylib::prelude::*;
 
 use crate::{
-    AppCtx, AppCtxUtils, Color as RlayColor, ContainerConfig, ContainerElement, Element,
-    InputState, KeyboardInput, MouseButtonState, MouseInput, RlayKeyboardKey, RootFactory,
-    TextConfig, TextDimensions,
+    AppCtx, AppCtxUtils, Color as RlayColor, ContainerConfig, ContainerElement, CorderRadius,
+    Element, FontCache, FontData, FontSource, ImageData, InputState, KeyboardInput,
+    MouseButtonState, MouseInput, RlayKeyboardKey, RootFactory, ScissorStack, TextConfig,
+    TextDimensions, TextureCache, Transform,
     colors::BLACK,
     commands::RlayDrawCommand,
     err::RlayError,
@@ -24,16 +27,19 @@ impl From<Color> for RlayColor {
     }
 }
 
-impl From<RlayKeyboardKey> for KeyboardKey {
-    fn from(value: RlayKeyboardKey) -> Self {
-        raylib::input::key_from_i32((value as u32) as i32).unwrap()
-    }
+/// Some keys have no raylib key, e.g. `KEY_NULL`, `KEY_BACK` or `KEY_VOLUME_UP`
+fn raylib_key(key: RlayKeyboardKey) -> Option<KeyboardKey> {
+    raylib::input::key_from_i32(key as i32)
 }
 
 pub struct RaylibRenderer {
     handle: RaylibHandle,
     thread: RaylibThread,
-    default_font: Option<raylib::text::Font>,
+    textures: TextureCache<Texture2D>,
+    /// The textures of the layers, by depth, reused from frame to frame
+    layers: Vec<RenderTexture2D>,
+    /// Shared with the function measuring the texts
+    fonts: Rc<RefCell<FontCache<Font>>>,
 }
 
 impl RaylibRenderer {
@@ -42,8 +48,169 @@ impl RaylibRenderer {
         Self {
             handle,
             thread,
-            default_font: None,
+            textures: TextureCache::default(),
+            layers: vec![],
+            fonts: Rc::default(),
+        }
+    }
+
+    /// The keys for which `is_key` is true
+    fn keys(&self, is_key: fn(&RaylibHandle, KeyboardKey) -> bool) -> HashSet<RlayKeyboardKey> {
+        RlayKeyboardKey::ALL
+            .into_iter()
+            .filter(|key| raylib_key(*key).is_some_and(|key| is_key(&self.handle, key)))
+            .collect()
+    }
+}
+
+fn load_texture(
+    handle: &mut RaylibHandle,
+    thread: &RaylibThread,
+    data: &ImageData,
+) -> Option<Texture2D> {
+    match data {
+        ImageData::File { path } => handle.load_texture(thread, path).ok(),
+        ImageData::Bytes { file_type, bytes } => {
+            // raylib expects the extension of the file, with its leading dot
+            let file_type = if file_type.starts_with('.') {
+                file_type.clone()
+            } else {
+                format!(".{}", file_type)
+            };
+            let image = Image::load_image_from_mem(&file_type, bytes).ok()?;
+            handle.load_texture_from_image(thread, &image).ok()
+        }
+    }
+}
+
+/// The font of the texts without a registered font. The default font of
+/// raylib is only used when it can't be loaded, e.g. when the app isn't run
+/// from the root of the repository.
+const DEFAULT_FONT: &str = "src/examples/assets/Roboto-VariableFont.ttf";
+
+/// The size the glyphs of the fonts are rasterized at, large enough for most
+/// texts to be drawn without being scaled up
+const FONT_LOAD_SIZE: i32 = 64;
+
+/// Loads the font through the ffi, as the measure function has no access to
+/// the raylib handle
+fn load_font(source: &FontSource) -> Option<Font> {
+    let font = match source {
+        FontSource::File { path } => {
+            let path = CString::new(path.as_str()).ok()?;
+            unsafe {
+                raylib::ffi::LoadFontEx(path.as_ptr(), FONT_LOAD_SIZE, std::ptr::null_mut(), 0)
+            }
+        }
+        FontSource::Bytes { bytes } => {
+            // raylib reads ttf and otf files the same way
+            let file_type = CString::new(".ttf").ok()?;
+            unsafe {
+                raylib::ffi::LoadFontFromMemory(
+                    file_type.as_ptr(),
+                    bytes.as_ptr(),
+                    bytes.len() as i32,
+                    FONT_LOAD_SIZE,
+                    std::ptr::null_mut(),
+                    0,
+                )
+            }
+        }
+    };
+
+    if font.glyphs.is_null() || font.texture.id == 0 {
+        return None;
+    }
+    Some(unsafe { Font::from_raw(font) })
+}
+
+/// The font to draw the text with, the default font of raylib when it has
+/// none or when it can't be loaded, as a last fallback
+fn text_font(fonts: &mut FontCache<Font>, font: Option<&FontData>) -> WeakFont {
+    let loaded = font.and_then(|font| fonts.get_or_load(&font.id, |_| load_font(&font.source)));
+    let raw = match loaded {
+        Some(font) => **font,
+        None => unsafe { raylib::ffi::GetFontDefault() },
+    };
+    unsafe { WeakFont::from_raw(raw) }
+}
+
+/// raylib can only round all the corners of a rect the same way
+fn has_same_corners(corner_radius: CorderRadius) -> bool {
+    let (top_left, top_right, bottom_left, bottom_right) = corner_radius.to_tuple();
+    top_left == top_right && top_left == bottom_left && top_left == bottom_right
+}
+
+/// raylib only draws the triangles going counter-clockwise on the screen
+fn is_clockwise(a: Point2D, b: Point2D, c: Point2D) -> bool {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x) > 0.0
+}
+
+fn draw_triangles(d: &mut impl RaylibDraw, vertices: &[Point2D], color: RlayColor) {
+    for triangle in vertices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+        let (b, c) = if is_clockwise(a, b, c) {
+            (c, b)
+        } else {
+            (b, c)
+        };
+        d.draw_triangle(
+            Vector2::new(a.x, a.y),
+            Vector2::new(b.x, b.y),
+            Vector2::new(c.x, c.y),
+            Color::from(color),
+        );
+    }
+}
+
+/// Applies the transform to the current matrix of rlgl
+fn apply_transform(pivot: Point2D, transform: Transform) {
+    unsafe {
+        raylib::ffi::rlTranslatef(
+            pivot.x + transform.translation.x,
+            pivot.y + transform.translation.y,
+            0.0,
+        );
+        raylib::ffi::rlRotatef(transform.angle.to_degrees(), 0.0, 0.0, 1.0);
+        raylib::ffi::rlScalef(transform.scale.x, transform.scale.y, 1.0);
+        raylib::ffi::rlTranslatef(-pivot.x, -pivot.y, 0.0);
+    }
+}
+
+/// Draws the triangles with a color for each vertex. raylib has no function
+/// for it, so the vertices are given to rlgl, like raylib does for its shapes.
+fn draw_colored_triangles(vertices: &[(Point2D, RlayColor)]) {
+    unsafe {
+        raylib::ffi::rlBegin(raylib::ffi::RL_TRIANGLES as i32);
+        for triangle in vertices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+            let (b, c) = if is_clockwise(a.0, b.0, c.0) {
+                (c, b)
+            } else {
+                (b, c)
+            };
+            for (point, color) in [a, b, c] {
+                let color = Color::from(color);
+                raylib::ffi::rlColor4ub(color.r, color.g, color.b, color.a);
+                raylib::ffi::rlVertex2f(point.x, point.y);
+            }
         }
+        raylib::ffi::rlEnd();
+    }
+}
+
+/// Restricts drawing to the area, or removes the restriction
+fn set_scissor(area: Option<(Point2D, Dimension2D)>) {
+    match area {
+        Some((position, dimensions)) => unsafe {
+            raylib::ffi::BeginScissorMode(
+                position.x as i32,
+                position.y as i32,
+                dimensions.width as i32,
+                dimensions.height as i32,
+            )
+        },
+        None => unsafe { raylib::ffi::EndScissorMode() },
     }
 }
 
@@ -53,44 +220,79 @@ impl RenderImpl for RaylibRenderer {
         R: RootFactory,
     {
         let mut renderer_impl = RaylibRenderer::new();
-        let font = renderer_impl
-            .handle
-            .load_font(
-                &renderer_impl.thread,
-                "src/examples/assets/Roboto-VariableFont.ttf",
-            )
-            .expect("loaded font");
-
-        let fn_font: text::Font = unsafe { text::Font::from_raw(font.clone()) };
-        let fns = AppCtxUtils {
-            measure_text: Box::new(move |text: &str, config: &TextConfig| -> TextDimensions {
+        let fonts = renderer_impl.fonts.clone();
+        let fns = AppCtxUtils::new(
+            move |text: &str, config: &TextConfig, font: Option<&FontData>| {
+                let font = text_font(&mut fonts.borrow_mut(), font);
                 let dim =
-                    raylib::text::Font::measure_text(&fn_font, text, config.font_size as f32, 1.0);
+                    font.measure_text(text, config.font_size as f32, config.letter_spacing as f32);
                 TextDimensions {
                     width: dim.x,
                     height: dim.y,
                     offset_y: 0.0,
                 }
-            }),
-            is_key_pressed: Box::new(|key| unsafe {
-                raylib::ffi::IsKeyPressed(KeyboardKey::from(key) as i32)
-            }),
-        };
+            },
+        );
 
         // renderer.handle.set_target_fps(200);
         let mut ctx = AppCtx::new(fns);
-
-        renderer_impl.default_font = Some(font);
+        ctx.set_default_font_file(DEFAULT_FONT);
 
         while !renderer_impl.handle.window_should_close() {
             let (new_ctx, draws) =
                 renderer::process_frame(&mut renderer_impl, ctx, root_factory.clone())
                     .expect("error when rendering frame");
 
+            // Textures must be uploaded before we start drawing the frame
+            for draw in draws.iter() {
+                if let RlayDrawCommand::DrawImage { data, .. } = draw {
+                    renderer_impl.textures.get_or_load(data, |data| {
+                        load_texture(&mut renderer_impl.handle, &renderer_impl.thread, data)
+                    });
+                }
+            }
+
+            // So are the layers, which cover the screen and are made again
+            // when it is resized
+            let (width, height) = (
+                renderer_impl.handle.get_screen_width(),
+                renderer_impl.handle.get_screen_height(),
+            );
+            renderer_impl
+                .layers
+                .retain(|layer| (layer.texture.width, layer.texture.height) == (width, height));
+            let nb_layers = draws
+                .iter()
+                .scan(0, |depth, draw| {
+                    match draw {
+                        RlayDrawCommand::BeginLayer => *depth += 1,
+                        RlayDrawCommand::EndLayer { .. } => *depth -= 1,
+                        _ => {}
+                    }
+                    Some(*depth)
+                })
+                .max()
+                .unwrap_or(0);
+            while renderer_impl.layers.len() < nb_layers {
+                let Ok(layer) = renderer_impl.handle.load_render_texture(
+                    &renderer_impl.thread,
+                    width as u32,
+                    height as u32,
+                ) else {
+                    // The children of the missing layers are drawn on the
+                    // layer under them
+                    break;
+                };
+                renderer_impl.layers.push(layer);
+            }
+
             {
-                let font = renderer_impl.default_font.as_ref().unwrap();
+                let mut fonts = renderer_impl.fonts.borrow_mut();
                 let mut d = renderer_impl.handle.begin_drawing(&renderer_impl.thread);
                 d.clear_background(Color::from(BLACK));
+                let mut scissors = ScissorStack::default();
+                let mut depth = 0;
+                let mut transforms = vec![];
                 for draw in draws {
                     match draw {
                         RlayDrawCommand::DrawRectangle {
@@ -115,20 +317,74 @@ impl RenderImpl for RaylibRenderer {
                             let Point2D { x, y } = position;
                             d.draw_circle_v(Vector2::new(x, y), radius, Color::from(color));
                         }
+                        RlayDrawCommand::DrawRoundedRect {
+                            position,
+                            dimensions,
+                            corner_radius,
+                            color,
+                        } if has_same_corners(corner_radius) => {
+                            let min_side = dimensions.width.min(dimensions.height);
+                            let radius = corner_radius.top_left.unwrap_or_default();
+                            d.draw_rectangle_rounded(
+                                Rectangle::new(
+                                    position.x,
+                                    position.y,
+                                    dimensions.width,
+                                    dimensions.height,
+                                ),
+                                // raylib wants the radius relative to the smallest side
+                                if min_side > 0.0 {
+                                    radius * 2.0 / min_side
+                                } else {
+                                    0.0
+                                },
+                                0,
+                                Color::from(color),
+                            );
+                        }
+                        RlayDrawCommand::DrawLine {
+                            start,
+                            end,
+                            thickness,
+                            color,
+                        } => {
+                            d.draw_line_ex(
+                                Vector2::new(start.x, start.y),
+                                Vector2::new(end.x, end.y),
+                                thickness,
+                                Color::from(color),
+                            );
+                        }
+                        RlayDrawCommand::DrawTriangles { vertices, color } => {
+                            draw_triangles(&mut d, &vertices, color);
+                        }
+                        command @ RlayDrawCommand::DrawGradient { .. } => {
+                            if let Some(vertices) = command.colored_triangles() {
+                                draw_colored_triangles(&vertices);
+                            }
+                        }
+                        command @ (RlayDrawCommand::DrawRoundedRect { .. }
+                        | RlayDrawCommand::DrawBorder { .. }
+                        | RlayDrawCommand::DrawPath { .. }) => {
+                            if let Some((vertices, color)) = command.triangles() {
+                                draw_triangles(&mut d, &vertices, color);
+                            }
+                        }
                         RlayDrawCommand::DrawText {
                             text,
                             position,
                             config,
+                            font,
                             ..
                         } => {
                             let Point2D { x, y } = position;
 
                             d.draw_text_codepoints(
-                                font,
+                                text_font(&mut fonts, font.as_ref()),
                                 &text,
                                 Vector2::new(x, y),
                                 config.font_size as f32,
-                                0.0,
+                                config.letter_spacing as f32,
                                 Color::from(config.color),
                             )
                         }
@@ -136,7 +392,86 @@ impl RenderImpl for RaylibRenderer {
                             data,
                             position,
                             dimensions,
-                        } => todo!(),
+                            opacity,
+                        } => {
+                            let Some(texture) = renderer_impl.textures.get(&data) else {
+                                continue;
+                            };
+                            let Point2D { x, y } = position;
+                            let Dimension2D { width, height } = dimensions;
+
+                            d.draw_texture_pro(
+                                texture,
+                                Rectangle::new(
+                                    0.0,
+                                    0.0,
+                                    texture.width as f32,
+                                    texture.height as f32,
+                                ),
+                                Rectangle::new(x, y, width, height),
+                                Vector2::zero(),
+                                0.0,
+                                Color::WHITE.alpha(opacity),
+                            );
+                        }
+                        RlayDrawCommand::BeginScissor {
+                            position,
+                            dimensions,
+                        } => {
+                            set_scissor(Some(scissors.push(position, dimensions)));
+                        }
+                        RlayDrawCommand::EndScissor => {
+                            set_scissor(scissors.pop());
+                        }
+                        RlayDrawCommand::BeginTransform { pivot, transform } => {
+                            unsafe { raylib::ffi::rlPushMatrix() };
+                            apply_transform(pivot, transform);
+                            transforms.push((pivot, transform));
+                        }
+                        RlayDrawCommand::EndTransform => {
+                            unsafe { raylib::ffi::rlPopMatrix() };
+                            transforms.pop();
+                        }
+                        RlayDrawCommand::BeginLayer => {
+                            if let Some(layer) = renderer_impl.layers.get(depth) {
+                                unsafe {
+                                    raylib::ffi::BeginTextureMode(**layer);
+                                    raylib::ffi::ClearBackground(Color::BLANK.into());
+                                }
+                                // The texture mode resets the transforms
+                                for &(pivot, transform) in &transforms {
+                                    apply_transform(pivot, transform);
+                                }
+                            }
+                            depth += 1;
+                        }
+                        RlayDrawCommand::EndLayer { opacity } => {
+                            depth -= 1;
+                            let Some(layer) = renderer_impl.layers.get(depth) else {
+                                continue;
+                            };
+                            unsafe {
+                                raylib::ffi::EndTextureMode();
+                                if let Some(under) = depth
+                                    .checked_sub(1)
+                                    .map(|depth| &renderer_impl.layers[depth])
+                                {
+                                    raylib::ffi::BeginTextureMode(**under);
+                                }
+                            }
+                            // The textures are upside down
+                            d.draw_texture_rec(
+                                layer.texture(),
+                                Rectangle::new(0.0, 0.0, width as f32, -height as f32),
+                                Vector2::zero(),
+                                Color::WHITE.alpha(opacity),
+                            );
+                            // What is in the layer is already transformed, the
+                            // transforms are only applied again after it
+                            for &(pivot, transform) in &transforms {
+                                apply_transform(pivot, transform);
+                            }
+                        }
                     }
                 }
             }
@@ -156,7 +491,7 @@ impl RenderImpl for RaylibRenderer {
                 ),
                 ..Default::default()
             },
-            None,
+            ctx.next_element_id(None, None, None),
         ));
 
         ctx.open_element(screen_root);
@@ -165,6 +500,7 @@ impl RenderImpl for RaylibRenderer {
     fn next_input_state(&mut self, ctx: &mut AppCtx) -> InputState {
         let mouse_position = self.handle.get_mouse_position();
         let mouse_delta = self.handle.get_mouse_delta();
+        let mouse_wheel = self.handle.get_mouse_wheel_move_v();
 
         InputState {
             mouse: MouseInput {
@@ -230,28 +566,14 @@ impl RenderImpl for RaylibRenderer {
                         MouseButtonState::Up
                     }
                 },
+                mouse_wheel: Point2D::new(mouse_wheel.x, mouse_wheel.y),
             },
             keyboard: KeyboardInput {
-                // keys_down: self
-                //     .handle
-                //     .get_key_pressed()
-                //     .into_iter()
-                //     .map(|code| code as u16)
-                //     .collect(),
-                // keys_released: self
-                //     .handle
-                //     .get_key()
-                //     .into_iter()
-                //     .map(|code| code as u16)
-                //     .collect(),
-                keys_pressed: self
-                    .handle
-                    .get_key_pressed()
-                    .into_iter()
-                    .map(|code| code as u16)
-                    .collect(),
-                last_char_pressed: self.handle.get_char_pressed(),
-                last_key_pressed: self.handle.get_key_pressed().map(|k| k as u16),
+                keys_down: self.keys(RaylibHandle::is_key_down),
+                keys_pressed: self.keys(RaylibHandle::is_key_pressed),
+                keys_released: self.keys(RaylibHandle::is_key_released),
+                keys_repeated: self.keys(RaylibHandle::is_key_pressed_repeat),
+                chars_pressed: std::iter::from_fn(|| self.handle.get_char_pressed()).collect(),
                 shift_down: self.handle.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
                     || self.handle.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT),
                 ctrl_down: self.handle.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
@@ -261,6 +583,7 @@ impl RenderImpl for RaylibRenderer {
                 super_down: self.handle.is_key_down(KeyboardKey::KEY_LEFT_SUPER)
                     || self.handle.is_key_down(KeyboardKey::KEY_RIGHT_SUPER),
             },
+            time: self.handle.get_time(),
         }
     }
 }
@@ -9,20 +9,319 @@ pub enum RlayDrawCommand {
         radius: f32,
         color: Color,
     },
+    /// A rect whose corners are rounded, with radii fitting in the rect
+    DrawRoundedRect {
+        position: Point2D,
+        dimensions: Dimension2D,
+        corner_radius: CorderRadius,
+        color: Color,
+    },
+    /// A rect filled with the gradient, whose corners are rounded like the ones
+    /// of a `DrawRoundedRect`
+    DrawGradient {
+        position: Point2D,
+        dimensions: Dimension2D,
+        corner_radius: CorderRadius,
+        gradient: Gradient,
+    },
+    /// A side of the border of the element at `position`, following its
+    /// rounded corners
+    DrawBorder {
+        position: Point2D,
+        dimensions: Dimension2D,
+        corner_radius: CorderRadius,
+        border: BorderConfig,
+        side: BorderSide,
+    },
+    DrawLine {
+        start: Point2D,
+        end: Point2D,
+        thickness: f32,
+        color: Color,
+    },
+    DrawPath {
+        segments: Vec<PathSegment>,
+        style: PathStyle,
+        color: Color,
+    },
+    /// Triangles, each made of three consecutive vertices
+    DrawTriangles {
+        vertices: Vec<Point2D>,
+        color: Color,
+    },
+    /// A single line of text
     DrawText {
         text: String,
         position: Point2D,
         dimensions: Dimension2D,
         config: TextConfig,
+        /// The font of the text, `None` when it uses the default font
+        font: Option<FontData>,
     },
     DrawImage {
         data: ImageData,
         position: Point2D,
         dimensions: Dimension2D,
+        /// Multiplies the alpha of the image
+        opacity: f32,
+    },
+    /// Only draw inside of this rect, until the matching `EndScissor`
+    BeginScissor {
+        position: Point2D,
+        dimensions: Dimension2D,
+    },
+    EndScissor,
+    /// Draw on a new transparent layer, until the matching `EndLayer`
+    BeginLayer,
+    /// Draws the layer over what is under it, with the opacity
+    EndLayer {
+        opacity: f32,
+    },
+    /// Draw with the transform, until the matching `EndTransform`. The
+    /// transforms of nested elements add up.
+    BeginTransform {
+        /// Where the origin of the transform is on the screen
+        pivot: Point2D,
+        transform: Transform,
     },
+    EndTransform,
 }
 
-use crate::{Color, Dimension2D, Element, ImageData, Point2D, TextConfig};
+use crate::{
+    BorderConfig, Color, CorderRadius, Dimension2D, Element, FontData, Gradient, ImageData,
+    Point2D, TextConfig, Transform, render::shapes,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderSide {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl BorderSide {
+    pub const ALL: [BorderSide; 4] = [Self::Top, Self::Right, Self::Bottom, Self::Left];
+}
+
+/// A part of a [`RlayDrawCommand::DrawPath`], going from the end of the
+/// previous one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    /// Starts a new part of the path at the point
+    MoveTo(Point2D),
+    LineTo(Point2D),
+    /// A quadratic bézier curve
+    QuadTo {
+        control: Point2D,
+        to: Point2D,
+    },
+    /// A cubic bézier curve
+    CubicTo {
+        control1: Point2D,
+        control2: Point2D,
+        to: Point2D,
+    },
+    /// Goes back to the start of the current part of the path
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathStyle {
+    Fill,
+    Stroke { thickness: f32 },
+}
+
+impl RlayDrawCommand {
+    /// The triangles of the shape drawn by the command, and their color. A
+    /// renderer which can't draw a shape natively can draw these instead.
+    /// Texts, images, gradients and the commands changing how the others are
+    /// drawn have no triangles, see [`Self::colored_triangles`] for the
+    /// gradients.
+    pub fn triangles(&self) -> Option<(Vec<Point2D>, Color)> {
+        match self {
+            Self::DrawRectangle {
+                position,
+                dimensions,
+                color,
+            } => Some((
+                shapes::rounded_rectangle(*position, *dimensions, CorderRadius::default()),
+                *color,
+            )),
+            Self::DrawCircle {
+                position,
+                radius,
+                color,
+            } => Some((
+                shapes::rounded_rectangle(
+                    *position - Point2D::scalar(*radius),
+                    Dimension2D::scalar(radius * 2.0),
+                    CorderRadius::from(*radius),
+                ),
+                *color,
+            )),
+            Self::DrawRoundedRect {
+                position,
+                dimensions,
+                corner_radius,
+                color,
+            } => Some((
+                shapes::rounded_rectangle(*position, *dimensions, *corner_radius),
+                *color,
+            )),
+            Self::DrawBorder {
+                position,
+                dimensions,
+                corner_radius,
+                border,
+                side,
+            } => Some((
+                shapes::border(*position, *dimensions, *corner_radius, border, *side),
+                border.color,
+            )),
+            Self::DrawLine {
+                start,
+                end,
+                thickness,
+                color,
+            } => Some((shapes::line(*start, *end, *thickness), *color)),
+            Self::DrawPath {
+                segments,
+                style,
+                color,
+            } => Some((
+                match style {
+                    PathStyle::Fill => shapes::fill_path(segments),
+                    PathStyle::Stroke { thickness } => shapes::stroke_path(segments, *thickness),
+                },
+                *color,
+            )),
+            Self::DrawTriangles { vertices, color } => Some((vertices.clone(), *color)),
+            Self::DrawGradient { .. }
+            | Self::DrawText { .. }
+            | Self::DrawImage { .. }
+            | Self::BeginScissor { .. }
+            | Self::EndScissor
+            | Self::BeginLayer
+            | Self::EndLayer { .. }
+            | Self::BeginTransform { .. }
+            | Self::EndTransform => None,
+        }
+    }
+
+    /// Multiplies the alpha of what the command draws
+    pub fn fade(&mut self, opacity: f32) {
+        let fade = |color: &mut Color| color.a *= opacity;
+        match self {
+            Self::DrawRectangle { color, .. }
+            | Self::DrawCircle { color, .. }
+            | Self::DrawRoundedRect { color, .. }
+            | Self::DrawLine { color, .. }
+            | Self::DrawPath { color, .. }
+            | Self::DrawTriangles { color, .. } => fade(color),
+            Self::DrawBorder { border, .. } => fade(&mut border.color),
+            Self::DrawText { config, .. } => fade(&mut config.color),
+            Self::DrawGradient { gradient, .. } => match gradient {
+                Gradient::Linear { stops, .. } | Gradient::Radial { stops, .. } => {
+                    stops.iter_mut().for_each(|stop| fade(&mut stop.color))
+                }
+            },
+            Self::DrawImage { opacity: alpha, .. } | Self::EndLayer { opacity: alpha } => {
+                *alpha *= opacity
+            }
+            Self::BeginScissor { .. }
+            | Self::EndScissor
+            | Self::BeginLayer
+            | Self::BeginTransform { .. }
+            | Self::EndTransform => {}
+        }
+    }
+
+    /// The triangles of the shape drawn by the command, with the color of each
+    /// of their vertices, which is how gradients are drawn without shaders
+    pub fn colored_triangles(&self) -> Option<Vec<(Point2D, Color)>> {
+        match self {
+            Self::DrawGradient {
+                position,
+                dimensions,
+                corner_radius,
+                gradient,
+            } => Some(shapes::gradient(
+                *position,
+                *dimensions,
+                *corner_radius,
+                gradient,
+            )),
+            command => {
+                let (vertices, color) = command.triangles()?;
+                Some(vertices.into_iter().map(|vertex| (vertex, color)).collect())
+            }
+        }
+    }
+
+    /// The circles and rectangles drawing a `DrawRoundedRect`, for a renderer
+    /// which can't draw triangles: a circle in each corner, under a rectangle
+    /// along each side and one in the center. They overlap, so a translucent
+    /// rect is darker where they do, [`Self::triangles`] are better when they
+    /// can be drawn.
+    pub fn circles_and_rectangles(&self) -> Option<Vec<RlayDrawCommand>> {
+        let Self::DrawRoundedRect {
+            position,
+            dimensions,
+            corner_radius,
+            color,
+        } = *self
+        else {
+            return None;
+        };
+        let (top_left, top_right, bottom_left, bottom_right) = corner_radius.to_tuple();
+        let circle = |center: Point2D, radius: f32| Self::DrawCircle {
+            position: position + center,
+            radius,
+            color,
+        };
+        let rectangle = |offset: Point2D, dimensions: Dimension2D| Self::DrawRectangle {
+            position: position + offset,
+            dimensions,
+            color,
+        };
+        let Dimension2D { width, height } = dimensions;
+        let left = top_left.max(bottom_left);
+        let right = top_right.max(bottom_right);
+        let top = top_left.max(top_right);
+        let bottom = bottom_left.max(bottom_right);
+
+        Some(vec![
+            circle(Point2D::scalar(top_left), top_left),
+            circle(Point2D::new(width - top_right, top_right), top_right),
+            circle(Point2D::new(bottom_left, height - bottom_left), bottom_left),
+            circle(
+                Point2D::new(width - bottom_right, height - bottom_right),
+                bottom_right,
+            ),
+            rectangle(
+                Point2D::new(0.0, top_left),
+                Dimension2D::new(left, height - top_left - bottom_left),
+            ),
+            rectangle(
+                Point2D::new(width - right, top_right),
+                Dimension2D::new(right, height - top_right - bottom_right),
+            ),
+            rectangle(
+                Point2D::new(top_left, 0.0),
+                Dimension2D::new(width - top_left - top_right, top),
+            ),
+            rectangle(
+                Point2D::new(bottom_left, height - bottom),
+                Dimension2D::new(width - bottom_left - bottom_right, bottom),
+            ),
+            rectangle(
+                Point2D::new(left, top),
+                Dimension2D::new(width - left - right, height - top - bottom),
+            ),
+        ])
+    }
+}
 
 pub trait DrawCommand {
     fn draw(&self);
@@ -31,3 +330,71 @@ pub trait DrawCommand {
 impl DrawCommand for Element {
     fn draw(&self) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cover_a_rounded_rect_with_circles_and_rectangles() {
+        let (width, height) = (100.0, 60.0);
+        let corner_radius = CorderRadius::new(Some(10.0), Some(20.0), Some(5.0), None);
+        let rounded_rect = RlayDrawCommand::DrawRoundedRect {
+            position: Point2D::new(10.0, 20.0),
+            dimensions: Dimension2D::new(width, height),
+            corner_radius,
+            color: Color::default(),
+        };
+        let shapes = rounded_rect.circles_and_rectangles().unwrap();
+        let covered = |point: Point2D| {
+            shapes.iter().any(|shape| match *shape {
+                RlayDrawCommand::DrawCircle {
+                    position, radius, ..
+                } => {
+                    let to_center = point - position;
+                    to_center.x.powi(2) + to_center.y.powi(2) <= radius.powi(2)
+                }
+                RlayDrawCommand::DrawRectangle {
+                    position,
+                    dimensions,
+                    ..
+                } => {
+                    let end = position + dimensions.into();
+                    (position.x..=end.x).contains(&point.x)
+                        && (position.y..=end.y).contains(&point.y)
+                }
+                _ => false,
+            })
+        };
+        // The center of the arc of each corner, with the direction of the
+        // corner from it
+        let (top_left, top_right, bottom_left, bottom_right) = corner_radius.to_tuple();
+        let corners = [
+            (top_left, top_left, -1.0, -1.0, top_left),
+            (width - top_right, top_right, 1.0, -1.0, top_right),
+            (bottom_left, height - bottom_left, -1.0, 1.0, bottom_left),
+            (
+                width - bottom_right,
+                height - bottom_right,
+                1.0,
+                1.0,
+                bottom_right,
+            ),
+        ];
+        // Whether the point of the rect is past the arc of a corner
+        let cut_off = |point: Point2D| {
+            corners.iter().any(|&(x, y, to_x, to_y, radius)| {
+                let (dx, dy) = (point.x - x, point.y - y);
+                dx * to_x > 0.0 && dy * to_y > 0.0 && dx.powi(2) + dy.powi(2) > radius.powi(2)
+            })
+        };
+
+        for x in 0..100 {
+            for y in 0..60 {
+                let point = Point2D::new(x as f32 + 0.3, y as f32 + 0.3);
+                let on_screen = Point2D::new(10.0, 20.0) + point;
+                assert_eq!(covered(on_screen), !cut_off(point), "at {point:?}");
+            }
+        }
+    }
+}
@@ -1,19 +1,38 @@
+use std::{collections::HashMap, hash::Hash};
+
 use crate::{
-    AppCtx, Color, Dimension2D, InputState, Point2D, TextConfig, err::RlayError,
-    render::commands::RlayDrawCommand,
+    AppCtx, Background, BorderConfig, BorderMode, BorderWidth, Color, CorderRadius, Dimension2D,
+    FontData, ImageData, InputState, Point2D, ShadowConfig, TextConfig, Transform,
+    err::RlayError,
+    render::commands::{BorderSide, RlayDrawCommand},
 };
 
 pub mod commands;
+mod shapes;
 
 pub mod renderer {
     use crate::{
         AppCtx, Dimension2D, Done, Element, ElementLayout, Point2D, RenderImpl, RootFactory,
-        calculate_layout,
+        Transform, calculate_layout,
         err::RlayError,
-        render::{commands::RlayDrawCommand, draw_circle_cmd, draw_rectangle_cmd, draw_text_cmd},
+        render::{
+            commands::RlayDrawCommand, draw_background_cmd, draw_border_cmds, draw_image_cmd,
+            draw_scissor_cmd, draw_shadow_cmds, draw_text_cmd,
+        },
     };
 
-    fn process_element(ctx: &AppCtx, element: &ElementLayout<Done>) -> Vec<RlayDrawCommand> {
+    /// Commands of the floating elements, with their z-index
+    type FloatingLayers = Vec<(u16, Vec<RlayDrawCommand>)>;
+
+    /// The transforms of the ancestors of an element, with their pivot
+    type Transforms = Vec<(Point2D, Transform)>;
+
+    fn process_element(
+        ctx: &AppCtx,
+        element: &ElementLayout<Done>,
+        floating: &mut FloatingLayers,
+        transforms: &mut Transforms,
+    ) -> Vec<RlayDrawCommand> {
         let el_pos = element.position();
         let el_dim = element.dimensions();
 
@@ -21,168 +40,173 @@ pub mod renderer {
 
         match element.data() {
             Element::Container(container) => {
-                let bg_color = container.config().background_color;
-
-                // To draw rounded corners, we draw 4 rectangles in a "+" shape
-                // and 4 circles in the corners
-                if let Some(corner_radius) = container.config.corner_radius {
-                    let (corner_pos, corner_dim) = corner_radius.to_corner_layout();
-                    let (top_left, top_right, bottom_left, bottom_right) =
-                        corner_radius.to_tuple_capped(el_dim.width.min(el_dim.height) / 2.0);
+                let background = container.config().background.clone();
 
-                    // ------- START draw the corners -------
+                let corner_radius = container.config.corner_radius.map(|corner_radius| {
+                    corner_radius.capped(el_dim.width.min(el_dim.height) / 2.0)
+                });
 
-                    if let Some(bg_color) = bg_color {
-                        // We do this first, so they are drawn over by the rectangles
+                let transform = container
+                    .config
+                    .transform
+                    .map(|transform| (transform.pivot(el_pos, el_dim), transform));
+                transforms.extend(transform);
 
-                        // top left
-                        commands.push(draw_circle_cmd(
-                            el_pos + Point2D::scalar(top_left),
-                            top_left,
-                            bg_color,
-                        ));
+                let shadow = container.config.shadow;
+                if let Some(shadow) = shadow.filter(|shadow| !shadow.inset) {
+                    commands.extend(draw_shadow_cmds(el_pos, el_dim, corner_radius, &shadow));
+                }
 
-                        // top right
-                        commands.push(draw_circle_cmd(
-                            el_pos + Point2D::new(el_dim.width - top_right, top_right),
-                            top_right,
-                            bg_color,
-                        ));
+                if let Some(background) = background {
+                    commands.push(draw_background_cmd(
+                        el_pos,
+                        el_dim,
+                        corner_radius,
+                        background,
+                    ));
+                }
 
-                        // bottom left
-                        commands.push(draw_circle_cmd(
-                            el_pos + Point2D::new(bottom_left, el_dim.height - bottom_left),
-                            bottom_left,
-                            bg_color,
-                        ));
+                if let Some(shadow) = shadow.filter(|shadow| shadow.inset) {
+                    commands.extend(draw_shadow_cmds(el_pos, el_dim, corner_radius, &shadow));
+                }
 
-                        // bottom right
-                        commands.push(draw_circle_cmd(
-                            el_pos
-                                + Point2D::new(
-                                    el_dim.width - bottom_right,
-                                    el_dim.height - bottom_right,
-                                ),
-                            bottom_right,
-                            bg_color,
-                        ));
+                // The border is drawn over the background, which it covers
+                // when it is inset
+                if let Some(border) = container.config.border {
+                    commands.extend(draw_border_cmds(
+                        el_pos,
+                        el_dim,
+                        corner_radius.unwrap_or_default(),
+                        border,
+                    ));
+                }
 
-                        // ------- END draw the corners -------
+                let scroll_enabled = container.config().scroll.is_enabled();
+                if scroll_enabled {
+                    commands.push(draw_scissor_cmd(el_pos, el_dim, transforms));
+                }
 
-                        // draw the left rectangle
-                        commands.push(draw_rectangle_cmd(
-                            el_pos + Point2D::new(0.0, top_left),
-                            Dimension2D::new(
-                                top_left.max(bottom_left),
-                                el_dim.height - top_left - bottom_left,
-                            ),
-                            bg_color,
-                        ));
+                let floating_start = floating.len();
+                for child in element.children() {
+                    let z_index = match child.data() {
+                        Element::Container(container) => {
+                            container.config().floating.as_ref().map(|f| f.z_index)
+                        }
+                        _ => None,
+                    };
+
+                    match z_index {
+                        Some(z_index) => {
+                            // The slot is reserved before processing the child, so a
+                            // floating element is drawn before the ones it contains
+                            let layer_idx = floating.len();
+                            floating.push((z_index, vec![]));
+                            floating[layer_idx].1 =
+                                process_element(ctx, child, floating, transforms);
+                        }
+                        None => commands.extend(process_element(ctx, child, floating, transforms)),
+                    }
+                }
 
-                        // draw the right rectangle
-                        commands.push(draw_rectangle_cmd(
-                            el_pos
-                                + Point2D::new(
-                                    el_dim.width - top_right.max(bottom_right),
-                                    top_right,
-                                ),
-                            Dimension2D::new(
-                                top_right.max(bottom_right),
-                                el_dim.height - top_right - bottom_right,
-                            ),
-                            bg_color,
-                        ));
+                if scroll_enabled {
+                    commands.push(RlayDrawCommand::EndScissor);
+                }
 
-                        // draw the top rectangle
-                        commands.push(draw_rectangle_cmd(
-                            el_pos + Point2D::new(top_left, 0.0),
-                            Dimension2D::new(
-                                el_dim.width - top_left - top_right,
-                                top_left.max(top_right),
-                            ),
-                            bg_color,
-                        ));
+                // The transform goes in the layer, which is drawn as it is
+                if let Some((pivot, transform)) = transform {
+                    transforms.pop();
+                    let begin = || RlayDrawCommand::BeginTransform { pivot, transform };
+                    commands.insert(0, begin());
+                    commands.push(RlayDrawCommand::EndTransform);
+                    for (_, layer) in &mut floating[floating_start..] {
+                        layer.insert(0, begin());
+                        layer.push(RlayDrawCommand::EndTransform);
+                    }
+                }
 
-                        // draw the bottom rectangle
-                        commands.push(draw_rectangle_cmd(
-                            el_pos
-                                + Point2D::new(
-                                    bottom_left,
-                                    el_dim.height - bottom_left.max(bottom_right),
-                                ),
-                            Dimension2D::new(
-                                el_dim.width - bottom_left - bottom_right,
-                                bottom_left.max(bottom_right),
-                            ),
-                            bg_color,
+                let opacity = container.config.opacity.unwrap_or(1.0);
+                if container.config.layer {
+                    commands.insert(0, RlayDrawCommand::BeginLayer);
+                    commands.push(RlayDrawCommand::EndLayer { opacity });
+                } else if opacity < 1.0 {
+                    fade(&mut commands, opacity);
+                }
+                // The floating children are drawn later, out of the layer
+                if opacity < 1.0 {
+                    for (_, layer) in &mut floating[floating_start..] {
+                        fade(layer, opacity);
+                    }
+                }
+            }
+            Element::Text(text) => {
+                let config = text.config();
+                let font = ctx.fonts().resolve(config);
+                let font_size = config.font_size as f32;
+                for line in text.lines() {
+                    // The characters are centered in the height of the line
+                    let line_pos = el_pos
+                        + line.position
+                        + Point2D::new(0.0, (line.dimensions.height - font_size) / 2.0);
+                    if line.words.is_empty() {
+                        commands.push(draw_text_cmd(
+                            &line.text,
+                            line_pos,
+                            line.dimensions.with_height(font_size),
+                            config,
+                            font,
                         ));
+                        continue;
+                    }
 
-                        // draw the center rectangle
-                        commands.push(draw_rectangle_cmd(
-                            el_pos
-                                + Point2D::new(top_left.max(bottom_left), top_left.max(top_right)),
-                            el_dim
-                                - Dimension2D::new(
-                                    top_left.max(bottom_left) + top_right.max(bottom_right),
-                                    top_left.max(top_right) + bottom_left.max(bottom_right),
-                                ),
-                            bg_color,
+                    // The words of a justified line are drawn one by one to
+                    // spread them over the line
+                    for word in &line.words {
+                        commands.push(draw_text_cmd(
+                            &line.text[word.range.clone()],
+                            line_pos + Point2D::new(word.x, 0.0),
+                            Dimension2D::new(word.width, font_size),
+                            config,
+                            font,
                         ));
                     }
-                } else if let Some(border) = container.config.border {
-                    let (border_pos, border_dim) = border.width.to_border_layout();
-
-                    match border.mode {
-                        crate::BorderMode::Outset => {
-                            commands.push(draw_rectangle_cmd(
-                                el_pos - border_pos,
-                                el_dim + border_dim,
-                                border.color,
-                            ));
-                            if let Some(bg_color) = bg_color {
-                                commands.push(draw_rectangle_cmd(el_pos, el_dim, bg_color));
-                            }
-                        }
-                        crate::BorderMode::Inset => {
-                            commands.push(draw_rectangle_cmd(el_pos, el_dim, border.color));
-                            if let Some(bg_color) = bg_color {
-                                commands.push(draw_rectangle_cmd(
-                                    el_pos + border_pos,
-                                    el_dim - border_dim,
-                                    bg_color,
-                                ));
-                            }
-                        }
-                        crate::BorderMode::Midset => {
-                            commands.push(draw_rectangle_cmd(
-                                el_pos - border_pos / Point2D::scalar(2.0),
-                                el_dim + border_dim / Dimension2D::scalar(2.0),
-                                border.color,
-                            ));
-                            if let Some(bg_color) = bg_color {
-                                commands.push(draw_rectangle_cmd(
-                                    el_pos + border_pos / Point2D::scalar(2.0),
-                                    el_dim - border_dim / Dimension2D::scalar(2.0),
-                                    bg_color,
-                                ));
-                            }
-                        }
-                    }
-                } else {
-                    if let Some(bg_color) = bg_color {
-                        commands.push(draw_rectangle_cmd(el_pos, el_dim, bg_color));
-                    }
                 }
+            }
+            Element::Image(image) => {
+                commands.push(draw_image_cmd(image.data(), el_pos, el_dim));
+            }
+        }
 
-                for child in element.children() {
-                    commands.extend(process_element(ctx, child));
+        commands
+    }
+
+    /// Multiplies the alpha of what the commands draw. The layers they contain
+    /// are drawn with their opacity once composited, so only that opacity is
+    /// multiplied, not the alpha of what is drawn in them.
+    fn fade(commands: &mut [RlayDrawCommand], opacity: f32) {
+        let mut depth = 0;
+        for command in commands {
+            match command {
+                RlayDrawCommand::BeginLayer => depth += 1,
+                RlayDrawCommand::EndLayer { .. } => {
+                    depth -= 1;
+                    if depth == 0 {
+                        command.fade(opacity);
+                    }
                 }
+                _ if depth == 0 => command.fade(opacity),
+                _ => {}
             }
-            Element::Text(text) => {
-                commands.push(draw_text_cmd(text.data(), el_pos, el_dim, text.config()));
-            }
-            Element::Image(image) => todo!(),
         }
+    }
+
+    /// Floating elements are drawn over the rest of the tree, ordered by their
+    /// z-index. Elements with the same z-index keep the order of the tree.
+    fn process_root(ctx: &AppCtx, root: &ElementLayout<Done>) -> Vec<RlayDrawCommand> {
+        let mut floating = vec![];
+        let mut commands = process_element(ctx, root, &mut floating, &mut vec![]);
+
+        floating.sort_by_key(|(z_index, _)| *z_index);
+        commands.extend(floating.into_iter().flat_map(|(_, layer)| layer));
 
         commands
     }
@@ -210,11 +234,143 @@ pub mod renderer {
         let layout = calculate_layout(&ctx, elements)?;
 
         ctx.update_hovered_elements(&layout);
+        ctx.update_scroll_offsets(&layout);
+        ctx.dispatch_events();
 
-        let draws = process_element(&ctx, &layout);
+        let draws = process_root(&ctx, &layout);
 
         Ok((ctx, draws))
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{
+            AppCtxUtils, Background, BorderMode, Color, ContainerConfig, CorderRadius,
+            FloatingConfig, ShadowConfig, TextDimensions, render::commands::BorderSide,
+        };
+
+        /// The alpha of the rectangles, and the opacity of the layers, in the
+        /// order they are drawn
+        fn opacities(commands: &[RlayDrawCommand]) -> Vec<f32> {
+            commands
+                .iter()
+                .filter_map(|command| match command {
+                    RlayDrawCommand::DrawRectangle { color, .. } => Some(color.a),
+                    RlayDrawCommand::EndLayer { opacity } => Some(*opacity),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        #[test]
+        fn fade_the_layers_of_a_faded_container_once() {
+            let filled = ContainerConfig {
+                background: Some(Background::Color(Color::default())),
+                ..Default::default()
+            };
+            let layer = ContainerConfig {
+                layer: true,
+                ..filled.clone()
+            };
+            let mut ctx = AppCtx::new(AppCtxUtils::new(|_, _, _| TextDimensions {
+                width: 0.0,
+                height: 0.0,
+                offset_y: 0.0,
+            }));
+
+            let root = ContainerConfig {
+                opacity: Some(0.5),
+                ..filled.clone()
+            };
+            ctx.open_element(Element::container(root, "root".to_string()));
+            let faded_layer = ContainerConfig {
+                opacity: Some(0.5),
+                ..layer.clone()
+            };
+            ctx.open_element(Element::container(faded_layer, "layer".to_string()));
+            ctx.open_element(Element::container(filled.clone(), "a".to_string()));
+            ctx.close_element();
+            ctx.close_element();
+            let floating_layer = ContainerConfig {
+                floating: Some(FloatingConfig::default()),
+                ..layer
+            };
+            ctx.open_element(Element::container(floating_layer, "floating".to_string()));
+            ctx.open_element(Element::container(filled, "b".to_string()));
+            ctx.close_element();
+            ctx.close_element();
+            ctx.close_element();
+
+            let root = (&mut ctx).try_into().unwrap();
+            let layout = calculate_layout(&ctx, root).unwrap();
+            // What is drawn in a layer is only faded with the layer
+            assert_eq!(
+                opacities(&process_root(&ctx, &layout)),
+                [0.5, 1.0, 1.0, 0.25, 1.0, 1.0, 0.5]
+            );
+        }
+
+        #[test]
+        fn spread_the_layers_of_a_shadow_over_its_blur_radius() {
+            let shadow = ShadowConfig {
+                offset: Point2D::new(5.0, 5.0),
+                blur_radius: 8.0,
+                spread: 2.0,
+                color: Color::new_const(0.0, 0.0, 0.0, 0.5),
+                inset: false,
+            };
+            let dimensions = Dimension2D::new(100.0, 50.0);
+            let rounded = Some(CorderRadius::default().all(10.0));
+            let layers: Vec<_> = draw_shadow_cmds(Point2D::default(), dimensions, rounded, &shadow)
+                .into_iter()
+                .map(|command| match command {
+                    RlayDrawCommand::DrawRoundedRect {
+                        position,
+                        dimensions,
+                        corner_radius,
+                        color,
+                    } => (position, dimensions, corner_radius.to_tuple().0, color.a),
+                    _ => panic!("only the layers of the shadow are drawn"),
+                })
+                .collect();
+
+            // From 2 pixels smaller to 6 pixels bigger than the element, with
+            // the corners growing with them
+            assert_eq!(layers.len(), 4);
+            let (position, dimensions, radius, _) = layers[0];
+            assert_eq!(position, Point2D::new(7.0, 7.0));
+            assert_eq!(dimensions, Dimension2D::new(96.0, 46.0));
+            assert_eq!(radius, 8.0);
+            let (position, dimensions, radius, _) = layers[3];
+            assert_eq!(position, Point2D::new(-1.0, -1.0));
+            assert_eq!(dimensions, Dimension2D::new(112.0, 62.0));
+            assert_eq!(radius, 16.0);
+            // Where they all overlap, they have the color of the shadow
+            let clear = layers
+                .iter()
+                .map(|(.., alpha)| 1.0 - alpha)
+                .product::<f32>();
+            assert!((1.0 - clear - 0.5).abs() < 1e-5);
+
+            let inset = ShadowConfig {
+                offset: Point2D::new(5.0, 0.0),
+                blur_radius: 0.0,
+                spread: 0.0,
+                inset: true,
+                ..shadow
+            };
+            let sides: Vec<_> = draw_shadow_cmds(Point2D::default(), dimensions, None, &inset)
+                .into_iter()
+                .map(|command| match command {
+                    RlayDrawCommand::DrawBorder { border, side, .. } => (border.mode, side),
+                    _ => panic!("only the sides of the shadow are drawn"),
+                })
+                .collect();
+            // Moved to the right, the shadow only shows on the left
+            assert_eq!(sides, [(BorderMode::Inset, BorderSide::Left)]);
+        }
+    }
 }
 
 pub trait RenderImpl {
@@ -237,6 +393,86 @@ pub trait RenderImpl {
     }
 }
 
+/// Keeps the resources loaded by a backend, so they are only loaded the first
+/// time they are used.
+pub struct ResourceCache<K, T> {
+    resources: HashMap<K, Option<T>>,
+}
+
+/// The textures of the images, so they are only decoded the first time they are drawn
+pub type TextureCache<T> = ResourceCache<ImageData, T>;
+
+/// The fonts of the [`FontRegistry`](crate::FontRegistry), by id
+pub type FontCache<T> = ResourceCache<u16, T>;
+
+impl<K, T> Default for ResourceCache<K, T> {
+    fn default() -> Self {
+        Self {
+            resources: HashMap::new(),
+        }
+    }
+}
+
+impl<K, T> ResourceCache<K, T>
+where
+    K: Eq + Hash + Clone,
+{
+    pub fn get(&self, key: &K) -> Option<&T> {
+        self.resources.get(key).and_then(Option::as_ref)
+    }
+
+    /// Returns the resource, loading it with `load` if it is not in the cache.
+    /// A failed load is remembered so it is not retried every frame.
+    pub fn get_or_load<F>(&mut self, key: &K, load: F) -> Option<&T>
+    where
+        F: FnOnce(&K) -> Option<T>,
+    {
+        if !self.resources.contains_key(key) {
+            self.resources.insert(key.clone(), load(key));
+        }
+        self.get(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.resources.clear();
+    }
+}
+
+/// The scissor rects of a frame. A rect nested in another one is clipped by it.
+#[derive(Default)]
+pub struct ScissorStack {
+    rects: Vec<(Point2D, Dimension2D)>,
+}
+
+impl ScissorStack {
+    /// Pushes a rect, returning the area to draw in
+    pub fn push(&mut self, position: Point2D, dimensions: Dimension2D) -> (Point2D, Dimension2D) {
+        let rect = match self.rects.last() {
+            Some(&(parent_pos, parent_dim)) => {
+                let start =
+                    Point2D::new(position.x.max(parent_pos.x), position.y.max(parent_pos.y));
+                let end = Point2D::new(
+                    (position.x + dimensions.width).min(parent_pos.x + parent_dim.width),
+                    (position.y + dimensions.height).min(parent_pos.y + parent_dim.height),
+                );
+                (
+                    start,
+                    Dimension2D::new((end.x - start.x).max(0.0), (end.y - start.y).max(0.0)),
+                )
+            }
+            None => (position, dimensions),
+        };
+        self.rects.push(rect);
+        rect
+    }
+
+    /// Pops the last rect, returning the area to draw in, if there is still one
+    pub fn pop(&mut self) -> Option<(Point2D, Dimension2D)> {
+        self.rects.pop();
+        self.rects.last().copied()
+    }
+}
+
 pub trait RootFactory: Clone {
     fn apply(&self, ctx: AppCtx) -> Result<AppCtx, RlayError>;
 }
@@ -250,32 +486,210 @@ where
     }
 }
 
-fn draw_circle_cmd(position: Point2D, radius: f32, color: Color) -> RlayDrawCommand {
-    RlayDrawCommand::DrawCircle {
+fn draw_rectangle_cmd(position: Point2D, dimensions: Dimension2D, color: Color) -> RlayDrawCommand {
+    RlayDrawCommand::DrawRectangle {
         position,
-        radius,
+        dimensions,
         color,
     }
 }
 
-fn draw_rectangle_cmd(position: Point2D, dimensions: Dimension2D, color: Color) -> RlayDrawCommand {
-    RlayDrawCommand::DrawRectangle {
+/// The scissor of the rect, moved by the transforms of the element and its
+/// ancestors. A scissor can't be rotated, so it is the box around the moved rect.
+fn draw_scissor_cmd(
+    position: Point2D,
+    dimensions: Dimension2D,
+    transforms: &[(Point2D, Transform)],
+) -> RlayDrawCommand {
+    let end = position + dimensions.into();
+    let corners = [
         position,
-        dimensions,
-        color,
+        Point2D::new(end.x, position.y),
+        end,
+        Point2D::new(position.x, end.y),
+    ]
+    .map(|corner| {
+        transforms
+            .iter()
+            .rev()
+            .fold(corner, |point, (pivot, transform)| {
+                transform.apply(point, *pivot)
+            })
+    });
+
+    let start = corners
+        .into_iter()
+        .reduce(|a, b| Point2D::new(a.x.min(b.x), a.y.min(b.y)))
+        .unwrap_or(position);
+    let end = corners
+        .into_iter()
+        .reduce(|a, b| Point2D::new(a.x.max(b.x), a.y.max(b.y)))
+        .unwrap_or(end);
+    RlayDrawCommand::BeginScissor {
+        position: start,
+        dimensions: Dimension2D::new(end.x - start.x, end.y - start.y),
     }
 }
 
+/// A rect, rounded if it has a corner radius
+fn draw_rounded_rect_cmd(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: Option<CorderRadius>,
+    color: Color,
+) -> RlayDrawCommand {
+    match corner_radius {
+        Some(corner_radius) => RlayDrawCommand::DrawRoundedRect {
+            position,
+            dimensions,
+            corner_radius,
+            color,
+        },
+        None => draw_rectangle_cmd(position, dimensions, color),
+    }
+}
+
+fn draw_background_cmd(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: Option<CorderRadius>,
+    background: Background,
+) -> RlayDrawCommand {
+    match background {
+        Background::Color(color) => {
+            draw_rounded_rect_cmd(position, dimensions, corner_radius, color)
+        }
+        Background::Gradient(gradient) => RlayDrawCommand::DrawGradient {
+            position,
+            dimensions,
+            corner_radius: corner_radius.unwrap_or_default(),
+            gradient,
+        },
+    }
+}
+
+/// A command for each side of the border that has a width
+fn draw_border_cmds(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: CorderRadius,
+    border: BorderConfig,
+) -> impl Iterator<Item = RlayDrawCommand> {
+    let (left, top, right, bottom) = border.width.to_tuple();
+    BorderSide::ALL
+        .into_iter()
+        .zip([top, right, bottom, left])
+        .filter(|&(_, width)| width > 0.0)
+        .map(move |(side, _)| RlayDrawCommand::DrawBorder {
+            position,
+            dimensions,
+            corner_radius,
+            border,
+            side,
+        })
+}
+
+/// The shadow is drawn in layers spread over its blur radius, the smallest
+/// ones overlapping in its middle. Their color is lighter, so they add up to
+/// the color of the shadow where they all overlap.
+fn draw_shadow_cmds(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: Option<CorderRadius>,
+    shadow: &ShadowConfig,
+) -> Vec<RlayDrawCommand> {
+    let nb_layers = (shadow.blur_radius / 2.0).ceil().clamp(1.0, 16.0) as usize;
+    let color = Color {
+        a: 1.0 - (1.0 - shadow.color.a).powf(1.0 / nb_layers as f32),
+        ..shadow.color
+    };
+
+    let mut commands = vec![];
+    for layer in 0..nb_layers {
+        // How much the layer grows, from minus half of the blur radius to half of it
+        let blur = match nb_layers {
+            1 => 0.0,
+            _ => shadow.blur_radius * (layer as f32 / (nb_layers - 1) as f32 - 0.5),
+        };
+        let grow = shadow.spread + blur;
+
+        if shadow.inset {
+            // The part of the element outside of the moved and shrunk element
+            let width = |offset: f32| Some((grow + offset).max(0.0));
+            let border = BorderConfig {
+                color,
+                width: BorderWidth::new(
+                    width(shadow.offset.x),
+                    width(-shadow.offset.x),
+                    width(shadow.offset.y),
+                    width(-shadow.offset.y),
+                ),
+                mode: BorderMode::Inset,
+            };
+            commands.extend(draw_border_cmds(
+                position,
+                dimensions,
+                corner_radius.unwrap_or_default(),
+                border,
+            ));
+        } else {
+            let layer_dimensions = Dimension2D::new(
+                dimensions.width + grow * 2.0,
+                dimensions.height + grow * 2.0,
+            );
+            if layer_dimensions.width <= 0.0 || layer_dimensions.height <= 0.0 {
+                continue;
+            }
+            // The rounded corners grow with the layer, the square ones stay square
+            let layer_radius = corner_radius.map(|corner_radius| {
+                let (top_left, top_right, bottom_left, bottom_right) = corner_radius.to_tuple();
+                let radius = |radius: f32| {
+                    Some(if radius > 0.0 {
+                        (radius + grow).max(0.0)
+                    } else {
+                        0.0
+                    })
+                };
+                CorderRadius::new(
+                    radius(top_left),
+                    radius(top_right),
+                    radius(bottom_left),
+                    radius(bottom_right),
+                )
+                .capped(layer_dimensions.width.min(layer_dimensions.height) / 2.0)
+            });
+            commands.push(draw_rounded_rect_cmd(
+                position + shadow.offset - Point2D::scalar(grow),
+                layer_dimensions,
+                layer_radius,
+                color,
+            ));
+        }
+    }
+    commands
+}
+
 fn draw_text_cmd(
     text: &str,
     position: Point2D,
     dimensions: Dimension2D,
     config: &TextConfig,
+    font: Option<&FontData>,
 ) -> RlayDrawCommand {
     RlayDrawCommand::DrawText {
         text: text.to_string(),
         position,
         dimensions,
         config: config.clone(),
+        font: font.cloned(),
+    }
+}
+
+fn draw_image_cmd(data: &ImageData, position: Point2D, dimensions: Dimension2D) -> RlayDrawCommand {
+    RlayDrawCommand::DrawImage {
+        data: data.clone(),
+        position,
+        dimensions,
+        opacity: 1.0,
     }
 }
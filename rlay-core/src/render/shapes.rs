@@ -0,0 +1,575 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::{
+    BorderConfig, BorderMode, Color, CorderRadius, Dimension2D, Gradient, Point2D,
+    commands::{BorderSide, PathSegment},
+};
+
+/// The radii of the corners on both axes, clockwise from the top left corner
+type CornerRadii = [Point2D; 4];
+
+fn corner_radii(corner_radius: CorderRadius) -> CornerRadii {
+    let (top_left, top_right, bottom_left, bottom_right) = corner_radius.to_tuple();
+    [top_left, top_right, bottom_right, bottom_left].map(Point2D::scalar)
+}
+
+/// The number of segments of a corner, so larger corners stay smooth
+fn corner_segments(radius: Point2D) -> usize {
+    (radius.x.max(radius.y).sqrt() * 2.0)
+        .ceil()
+        .clamp(1.0, 32.0) as usize
+}
+
+/// The number of segments of a curve, from the length of its control points
+fn curve_segments(points: &[Point2D]) -> usize {
+    let length: f32 = points
+        .windows(2)
+        .map(|pair| ((pair[1].x - pair[0].x).powi(2) + (pair[1].y - pair[0].y).powi(2)).sqrt())
+        .sum();
+    (length / 2.0).ceil().clamp(1.0, 64.0) as usize
+}
+
+/// Twice the signed area of the triangle, positive when it goes clockwise on
+/// the screen
+fn orientation(a: Point2D, b: Point2D, c: Point2D) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn scaled(point: Point2D, factor: f32) -> Point2D {
+    point * Point2D::scalar(factor)
+}
+
+/// The outline of the rounded rect, clockwise from its left side. Each corner
+/// has `segments[i] + 1` points, so two rects with the same segments have
+/// matching outlines.
+fn outline(
+    position: Point2D,
+    dimensions: Dimension2D,
+    radii: CornerRadii,
+    segments: [usize; 4],
+) -> Vec<Point2D> {
+    let end = position + dimensions.into();
+    let centers = [
+        position + radii[0],
+        Point2D::new(end.x - radii[1].x, position.y + radii[1].y),
+        end - radii[2],
+        Point2D::new(position.x + radii[3].x, end.y - radii[3].y),
+    ];
+
+    let mut points = vec![];
+    for corner in 0..4 {
+        // The top left corner goes from the left to the top, and so on
+        let start_angle = (corner as f32 + 2.0) * FRAC_PI_2;
+        for step in 0..=segments[corner] {
+            let angle = start_angle + FRAC_PI_2 * step as f32 / segments[corner] as f32;
+            points.push(
+                centers[corner]
+                    + Point2D::new(radii[corner].x * angle.cos(), radii[corner].y * angle.sin()),
+            );
+        }
+    }
+    points
+}
+
+/// The triangles filling the rounded rect
+pub(crate) fn rounded_rectangle(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: CorderRadius,
+) -> Vec<Point2D> {
+    let radii = corner_radii(corner_radius);
+    let points = outline(position, dimensions, radii, radii.map(corner_segments));
+
+    // The rect is convex, so it is a fan around its center
+    let center = position + scaled(dimensions.into(), 0.5);
+    let mut triangles = Vec::with_capacity(points.len() * 3);
+    for (i, &point) in points.iter().enumerate() {
+        triangles.extend([center, point, points[(i + 1) % points.len()]]);
+    }
+    triangles
+}
+
+/// Keeps the part of the convex polygon where `distance` is positive. The
+/// distance must change linearly along the sides of the polygon.
+fn clip(polygon: &[Point2D], distance: impl Fn(Point2D) -> f32) -> Vec<Point2D> {
+    let mut clipped = vec![];
+    for (i, &point) in polygon.iter().enumerate() {
+        let next = polygon[(i + 1) % polygon.len()];
+        let (from, to) = (distance(point), distance(next));
+        if from >= 0.0 {
+            clipped.push(point);
+        }
+        if (from > 0.0 && to < 0.0) || (from < 0.0 && to > 0.0) {
+            clipped.push(point + scaled(next - point, from / (from - to)));
+        }
+    }
+    clipped
+}
+
+/// Adds the triangles of the convex polygon, with the color of each vertex
+fn fan(
+    polygon: &[Point2D],
+    color: impl Fn(Point2D) -> Color,
+    triangles: &mut Vec<(Point2D, Color)>,
+) {
+    for i in 1..polygon.len().saturating_sub(1) {
+        for point in [polygon[0], polygon[i], polygon[i + 1]] {
+            triangles.push((point, color(point)));
+        }
+    }
+}
+
+/// The triangles filling the rounded rect with the gradient, with the color of
+/// each vertex
+pub(crate) fn gradient(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: CorderRadius,
+    gradient: &Gradient,
+) -> Vec<(Point2D, Color)> {
+    let radii = corner_radii(corner_radius);
+    let shape = outline(position, dimensions, radii, radii.map(corner_segments));
+    let mut triangles = vec![];
+
+    match gradient {
+        Gradient::Linear { angle, stops } => {
+            let direction = Point2D::new(angle.cos(), angle.sin());
+            let length =
+                (dimensions.width * direction.x).abs() + (dimensions.height * direction.y).abs();
+            if length == 0.0 {
+                return triangles;
+            }
+            let center = position + scaled(dimensions.into(), 0.5);
+            let offset = |point: Point2D| {
+                let from_center = point - center;
+                (from_center.x * direction.x + from_center.y * direction.y) / length + 0.5
+            };
+
+            // The colors change linearly between two stops, so the band of the
+            // shape between them has the right colors with only its vertices
+            let mut bounds = vec![f32::NEG_INFINITY];
+            bounds.extend(stops.iter().map(|stop| stop.offset));
+            bounds.push(f32::INFINITY);
+            for band in bounds.windows(2) {
+                if band[1] <= band[0] {
+                    continue;
+                }
+                let part = clip(&shape, |point| offset(point) - band[0]);
+                let part = clip(&part, |point| band[1] - offset(point));
+                fan(
+                    &part,
+                    |point| gradient.color_at(offset(point)),
+                    &mut triangles,
+                );
+            }
+        }
+        Gradient::Radial {
+            center,
+            radius,
+            stops,
+        } => {
+            let center = position + Point2D::from(dimensions) * *center;
+            let end = position + dimensions.into();
+            let farthest = [
+                position,
+                Point2D::new(end.x, position.y),
+                end,
+                Point2D::new(position.x, end.y),
+            ]
+            .map(|corner| {
+                let to_corner = corner - center;
+                (to_corner.x.powi(2) + to_corner.y.powi(2)).sqrt()
+            })
+            .into_iter()
+            .fold(0.0, f32::max);
+            let radius = radius.unwrap_or(farthest).max(f32::EPSILON);
+            let offset = |point: Point2D| {
+                let from_center = point - center;
+                (from_center.x.powi(2) + from_center.y.powi(2)).sqrt() / radius
+            };
+
+            // The shape is covered by rings around the center, split at the
+            // stops, and each ring is made of sectors clipped to the shape
+            let mut rings = vec![0.0];
+            rings.extend(stops.iter().map(|stop| stop.offset.max(0.0) * radius));
+            rings.push(farthest.max(radius));
+            let nb_sectors = corner_segments(Point2D::scalar(farthest)) * 4;
+            let around = |distance: f32, sector: usize| {
+                let angle = 2.0 * PI * sector as f32 / nb_sectors as f32;
+                // The sides of the sectors are outside of the circle, so the
+                // sectors cover it
+                let distance = distance / (PI / nb_sectors as f32).cos();
+                center + Point2D::new(distance * angle.cos(), distance * angle.sin())
+            };
+
+            for ring in rings.windows(2) {
+                if ring[1] <= ring[0] {
+                    continue;
+                }
+                for sector in 0..nb_sectors {
+                    let mut part = vec![
+                        around(ring[0], sector),
+                        around(ring[1], sector),
+                        around(ring[1], sector + 1),
+                        around(ring[0], sector + 1),
+                    ];
+                    for (i, &from) in shape.iter().enumerate() {
+                        let to = shape[(i + 1) % shape.len()];
+                        part = clip(&part, |point| orientation(from, to, point));
+                    }
+                    fan(
+                        &part,
+                        |point| gradient.color_at(offset(point)),
+                        &mut triangles,
+                    );
+                }
+            }
+        }
+    }
+    triangles
+}
+
+/// The triangles of a side of the border of the element, whose corners have
+/// the `corner_radius`. The border follows the corners, rounded on both of its
+/// sides. Like in CSS, a corner is split between its two sides in proportion
+/// of their widths.
+pub(crate) fn border(
+    position: Point2D,
+    dimensions: Dimension2D,
+    corner_radius: CorderRadius,
+    border: &BorderConfig,
+    side: BorderSide,
+) -> Vec<Point2D> {
+    let (left, top, right, bottom) = border.width.to_tuple();
+    let outside = match border.mode {
+        BorderMode::Outset => 1.0,
+        BorderMode::Midset => 0.5,
+        BorderMode::Inset => 0.0,
+    };
+
+    let outer_position = position - scaled(Point2D::new(left, top), outside);
+    let outer_dimensions =
+        dimensions + Dimension2D::new(left + right, top + bottom) * Dimension2D::scalar(outside);
+    let inner_position = outer_position + Point2D::new(left, top);
+    let inner_dimensions = Dimension2D::new(
+        (outer_dimensions.width - left - right).max(0.0),
+        (outer_dimensions.height - top - bottom).max(0.0),
+    );
+
+    // The widths on both sides of each corner, clockwise from the top left one
+    let widths = [
+        Point2D::new(left, top),
+        Point2D::new(right, top),
+        Point2D::new(right, bottom),
+        Point2D::new(left, bottom),
+    ];
+    let radii = corner_radii(corner_radius);
+    let mut outer_radii = radii;
+    let mut inner_radii = radii;
+    for corner in 0..4 {
+        // A square corner stays square outside of the element
+        if radii[corner] != Point2D::default() {
+            outer_radii[corner] = radii[corner] + scaled(widths[corner], outside);
+        }
+        let inner = outer_radii[corner] - widths[corner];
+        inner_radii[corner] = Point2D::new(inner.x.max(0.0), inner.y.max(0.0));
+    }
+
+    let segments = outer_radii.map(corner_segments);
+    let outer = outline(outer_position, outer_dimensions, outer_radii, segments);
+    let inner = outline(inner_position, inner_dimensions, inner_radii, segments);
+
+    // The widths of the sides, clockwise from the top one. The corner `i`
+    // goes from the side before it to the side `i`.
+    let side_widths = [top, right, bottom, left];
+    let mut splits = [0; 4];
+    let mut corner_start = 0;
+    for corner in 0..4 {
+        let (from, to) = (side_widths[(corner + 3) % 4], side_widths[corner]);
+        let share = if from + to > 0.0 {
+            from / (from + to)
+        } else {
+            0.5
+        };
+        splits[corner] = corner_start + (segments[corner] as f32 * share).round() as usize;
+        corner_start += segments[corner] + 1;
+    }
+
+    let side = side as usize;
+    if side_widths[side] == 0.0 {
+        return vec![];
+    }
+    let start = splits[side];
+    let end = match side {
+        3 => splits[0] + outer.len(),
+        _ => splits[side + 1],
+    };
+
+    let mut triangles = vec![];
+    for i in start..end {
+        let (i, next) = (i % outer.len(), (i + 1) % outer.len());
+        triangles.extend([outer[i], outer[next], inner[next]]);
+        triangles.extend([outer[i], inner[next], inner[i]]);
+    }
+    triangles
+}
+
+/// The triangles of a straight line, with flat ends
+pub(crate) fn line(start: Point2D, end: Point2D, thickness: f32) -> Vec<Point2D> {
+    let direction = end - start;
+    let length = (direction.x.powi(2) + direction.y.powi(2)).sqrt();
+    if length == 0.0 {
+        return vec![];
+    }
+
+    let normal = scaled(
+        Point2D::new(-direction.y, direction.x),
+        thickness / 2.0 / length,
+    );
+    let [a, b, c, d] = [start + normal, end + normal, end - normal, start - normal];
+    vec![a, b, c, a, c, d]
+}
+
+/// The points of each part of the path, with whether it is closed. The curves
+/// are split into straight lines.
+fn flatten(segments: &[PathSegment]) -> Vec<(Vec<Point2D>, bool)> {
+    let mut parts = vec![];
+    let mut current: Vec<Point2D> = vec![];
+
+    for segment in segments {
+        let from = current.last().copied().unwrap_or_default();
+        match *segment {
+            PathSegment::MoveTo(to) => {
+                if current.len() > 1 {
+                    parts.push((std::mem::take(&mut current), false));
+                }
+                current = vec![to];
+            }
+            PathSegment::LineTo(to) => current.push(to),
+            PathSegment::QuadTo { control, to } => {
+                let steps = curve_segments(&[from, control, to]);
+                current.extend((1..=steps).map(|step| {
+                    let t = step as f32 / steps as f32;
+                    scaled(from, (1.0 - t).powi(2))
+                        + scaled(control, 2.0 * (1.0 - t) * t)
+                        + scaled(to, t.powi(2))
+                }));
+            }
+            PathSegment::CubicTo {
+                control1,
+                control2,
+                to,
+            } => {
+                let steps = curve_segments(&[from, control1, control2, to]);
+                current.extend((1..=steps).map(|step| {
+                    let t = step as f32 / steps as f32;
+                    scaled(from, (1.0 - t).powi(3))
+                        + scaled(control1, 3.0 * (1.0 - t).powi(2) * t)
+                        + scaled(control2, 3.0 * (1.0 - t) * t.powi(2))
+                        + scaled(to, t.powi(3))
+                }));
+            }
+            PathSegment::Close => {
+                if current.len() > 1 {
+                    let start = current[0];
+                    parts.push((std::mem::take(&mut current), true));
+                    current = vec![start];
+                }
+            }
+        }
+    }
+    if current.len() > 1 {
+        parts.push((current, false));
+    }
+    parts
+}
+
+/// The triangles of the lines of the path
+pub(crate) fn stroke_path(segments: &[PathSegment], thickness: f32) -> Vec<Point2D> {
+    let mut triangles = vec![];
+    for (points, closed) in flatten(segments) {
+        let nb_lines = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for i in 0..nb_lines {
+            triangles.extend(line(points[i], points[(i + 1) % points.len()], thickness));
+        }
+    }
+    triangles
+}
+
+/// The triangles filling each part of the path, which are closed if they are
+/// not already. The parts must not cross themselves, and don't make holes in
+/// each other.
+pub(crate) fn fill_path(segments: &[PathSegment]) -> Vec<Point2D> {
+    flatten(segments)
+        .into_iter()
+        .flat_map(|(points, _)| fill_polygon(&points))
+        .collect()
+}
+
+/// Splits the polygon into triangles by cutting its ears, the corners whose
+/// triangle has no other point of the polygon in it
+fn fill_polygon(points: &[Point2D]) -> Vec<Point2D> {
+    let polygon_orientation: f32 = (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    // Whether the triangle goes around in the same direction as the polygon
+    let same_direction =
+        |triangle_orientation: f32| triangle_orientation * polygon_orientation > 0.0;
+    let on_inner_side =
+        |triangle_orientation: f32| triangle_orientation * polygon_orientation >= 0.0;
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find_map(|i| {
+            let [a, b, c] = [(i + n - 1) % n, i, (i + 1) % n].map(|j| points[remaining[j]]);
+            let turn = orientation(a, b, c);
+            if turn == 0.0 {
+                // The point is on a straight line, it can go without a triangle
+                return Some((i, None));
+            }
+            if !same_direction(turn) {
+                return None;
+            }
+
+            let contains_point = remaining.iter().map(|&j| points[j]).any(|p| {
+                p != a
+                    && p != b
+                    && p != c
+                    && on_inner_side(orientation(a, b, p))
+                    && on_inner_side(orientation(b, c, p))
+                    && on_inner_side(orientation(c, a, p))
+            });
+            (!contains_point).then_some((i, Some([a, b, c])))
+        });
+
+        // A polygon crossing itself can run out of ears
+        let Some((i, triangle)) = ear else {
+            break;
+        };
+        triangles.extend(triangle.into_iter().flatten());
+        remaining.remove(i);
+    }
+    if remaining.len() == 3 {
+        triangles.extend(remaining.iter().map(|&i| points[i]));
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BorderWidth;
+
+    /// The area covered by the triangles, which are all clockwise
+    fn area(triangles: &[Point2D]) -> f32 {
+        triangles
+            .chunks(3)
+            .map(|triangle| {
+                let twice = orientation(triangle[0], triangle[1], triangle[2]);
+                assert!(twice >= -1e-3, "counterclockwise triangle {triangle:?}");
+                twice / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn fill_rounded_rects_with_clockwise_triangles() {
+        let (position, dimensions) = (Point2D::new(10.0, 20.0), Dimension2D::new(100.0, 50.0));
+
+        let square = rounded_rectangle(position, dimensions, CorderRadius::default());
+        assert_eq!(area(&square), 5000.0);
+
+        // The corners are cut off, a bit more than their quarter circles as
+        // they are made of segments
+        let rounded = rounded_rectangle(position, dimensions, CorderRadius::default().all(10.0));
+        let cut_off = 5000.0 - area(&rounded);
+        assert!((4.0 - PI) * 100.0 < cut_off && cut_off < (4.0 - PI) * 100.0 + 3.0);
+        assert!(rounded.iter().all(|point| {
+            (10.0..=110.0).contains(&point.x) && (20.0..=70.0).contains(&point.y)
+        }));
+    }
+
+    #[test]
+    fn draw_the_sides_of_a_border_outside_or_inside_of_the_element() {
+        let dimensions = Dimension2D::new(100.0, 50.0);
+        let sides = |mode, left| {
+            let config = BorderConfig {
+                color: Color::default(),
+                width: BorderWidth::new(left, Some(3.0), Some(2.0), Some(4.0)),
+                mode,
+            };
+            let radius = CorderRadius::default();
+            BorderSide::ALL
+                .map(|side| border(Point2D::default(), dimensions, radius, &config, side))
+        };
+        let total_area =
+            |sides: &[Vec<Point2D>; 4]| sides.iter().map(|side| area(side)).sum::<f32>();
+
+        // Around the element, then on both sides of its edges, then in it
+        let outset = sides(BorderMode::Outset, Some(1.0));
+        assert_eq!(total_area(&outset), 104.0 * 56.0 - 5000.0);
+        let midset = sides(BorderMode::Midset, Some(1.0));
+        assert_eq!(total_area(&midset), 102.0 * 53.0 - 98.0 * 47.0);
+        let inset = sides(BorderMode::Inset, Some(1.0));
+        assert_eq!(total_area(&inset), 5000.0 - 96.0 * 44.0);
+        let inside =
+            |point: &Point2D| (0.0..=100.0).contains(&point.x) && (0.0..=50.0).contains(&point.y);
+        assert!(inset.iter().flatten().all(inside));
+
+        // Each side is drawn on its own, the top one along the top edge
+        assert!(outset[0].iter().all(|point| point.y <= 0.0));
+        assert!(sides(BorderMode::Outset, None)[3].is_empty());
+    }
+
+    #[test]
+    fn fill_the_shape_with_the_colors_of_the_gradient() {
+        let (position, dimensions) = (Point2D::new(10.0, 20.0), Dimension2D::new(100.0, 50.0));
+        let red = Color::new_const(1.0, 0.0, 0.0, 1.0);
+        let blue = Color::new_const(0.0, 0.0, 1.0, 1.0);
+        let green = Color::new_const(0.0, 1.0, 0.0, 1.0);
+        let fill = |gradient| {
+            let triangles =
+                super::gradient(position, dimensions, CorderRadius::default(), &gradient);
+            let points: Vec<_> = triangles.iter().map(|(point, _)| *point).collect();
+            (area(&points), triangles)
+        };
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-3;
+
+        // From the left to the right, through green in the middle
+        let (linear_area, triangles) = fill(Gradient::linear(0.0, [red, green, blue]));
+        assert!(close(linear_area, 5000.0));
+        for (point, color) in triangles {
+            let offset = (point.x - 10.0) / 100.0;
+            assert!(close(color.r, (1.0 - offset * 2.0).max(0.0)));
+            assert!(close(color.g, 1.0 - (offset * 2.0 - 1.0).abs()));
+            assert!(close(color.b, (offset * 2.0 - 1.0).max(0.0)));
+        }
+
+        // From the center to the farthest corners
+        let (radial_area, triangles) = fill(Gradient::radial([red, blue]));
+        assert!(close(radial_area, 5000.0));
+        let color_at = |x, y| {
+            let vertices = triangles
+                .iter()
+                .filter(|(point, _)| point.x == x && point.y == y);
+            let colors: Vec<_> = vertices.map(|(_, color)| *color).collect();
+            assert!(!colors.is_empty());
+            colors
+        };
+        assert!(color_at(60.0, 45.0).iter().all(|color| *color == red));
+        assert!(
+            color_at(110.0, 70.0)
+                .iter()
+                .all(|color| close(color.b, 1.0))
+        );
+    }
+}
@@ -1,10 +1,20 @@
 use std::{
     any::Any,
+    cmp::Reverse,
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
-use crate::{Done, Element, ElementLayout, Point2D};
+use crate::{
+    Dimension2D, Done, Element, ElementLayout, Event, EventKind, FocusGroup, MouseButton, Point2D,
+    PointerCaptureMode,
+};
+
+/// The number of pixels scrolled for each step of the mouse wheel
+const SCROLL_SPEED: f32 = 30.0;
+
+/// The number of seconds between two clicks for them to be a double click
+const DOUBLE_CLICK_TIME: f64 = 0.5;
 
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct ElementState {
@@ -66,12 +76,34 @@ impl ElementState {
 pub struct AppState {
     hovered: HashSet<String>,
     active: HashSet<String>,
-    focusable: HashSet<String>,
+    /// The focusable elements, in the order of Tab
+    focusable: Vec<Focusable>,
+    /// The focus groups, with the group they are in
+    focus_groups: Vec<(FocusGroup, Option<usize>)>,
+    navigation_keys: Option<NavigationKeys>,
+    scrollable: HashSet<String>,
     focused: Option<String>,
+    scroll_offsets: HashMap<String, Point2D>,
     element_state: HashMap<String, ElementState>,
     input_state: InputState,
     input_state_init: bool,
 
+    /// The id of the parent of every element of the last layout
+    parents: HashMap<String, String>,
+    /// The elements under the cursor, the topmost first
+    pointer_over: Vec<String>,
+    /// The topmost element under the cursor catching the pointer, which the
+    /// clicks go to
+    pointer_target: Option<String>,
+    /// The path of the element the left button was pressed on
+    pressed_path: Vec<String>,
+    /// The element last clicked, and when
+    last_click: Option<(String, f64)>,
+    last_focused: Option<String>,
+    /// The focused element activated with Enter or Space, like a click
+    activated: Option<String>,
+    events: Vec<Event>,
+
     store: Arc<Mutex<HashMap<String, Box<dyn Any>>>>,
 }
 
@@ -99,11 +131,9 @@ impl AppState {
     }
 
     pub(crate) fn update_hovered_elements(&mut self, element: &ElementLayout<Done>) {
-        if !self.input_state_init {
-            return;
-        }
+        self.events.clear();
 
-        for hovered in self.hovered.iter() {
+        for hovered in self.hovered.iter().chain(self.activated.iter()) {
             get_mut_or_insert(&mut self.element_state, hovered.to_owned()).reset();
         }
 
@@ -115,50 +145,443 @@ impl AppState {
             self.active.clear();
         }
 
-        self.hovered.clear();
-        self.focusable.clear();
-        self._update_hovered_elements(element);
+        let previous_hovered = std::mem::take(&mut self.hovered);
+        self.scrollable.clear();
+        self.parents.clear();
+
+        let mut hit_test = HitTest::default();
+        let root_layer = HitLayer {
+            captures: true,
+            ..Default::default()
+        };
+        self.hit_test(
+            element,
+            self.input_state.mouse.mouse_position,
+            true,
+            root_layer,
+            &mut hit_test,
+        );
+        // Without a tab index, the elements keep the order of the tree
+        self.focusable = std::mem::take(&mut hit_test.focusable);
+        self.focusable
+            .sort_by_key(|focusable| (focusable.tab_index.is_none(), focusable.tab_index));
+        self.focus_groups = std::mem::take(&mut hit_test.focus_groups);
+        self.update_focus();
 
-        for hovered in self.hovered.iter() {
-            let state = get_mut_or_insert(&mut self.element_state, hovered.to_owned());
+        // The cursor is over nothing until the backend knows where it is, but
+        // the focus and the keys work without it
+        if self.input_state_init {
+            let hits = hit_test.pointer_over();
+            self.pointer_target = hits.iter().find(|hit| hit.target).map(|hit| hit.id.clone());
+            self.pointer_over = hits.into_iter().map(|hit| hit.id).collect();
+            self.hovered = self.pointer_over.iter().cloned().collect();
+        }
+
+        self.queue_events(element, previous_hovered);
+
+        if let Some(target) = &self.pointer_target {
+            let state = get_mut_or_insert(&mut self.element_state, target.to_owned());
             state.is_clicked = left_clicked;
 
             state.is_pressed = pressed;
 
             if state.is_pressed {
-                self.active.insert(hovered.to_owned());
+                self.active.insert(target.to_owned());
             }
 
             state.is_right_clicked = right_clicked;
         }
+
+        if let Some(activated) = &self.activated {
+            get_mut_or_insert(&mut self.element_state, activated.to_owned()).is_clicked = true;
+        }
+    }
+
+    /// Moves the focus with Tab and Shift+Tab or the navigation keys,
+    /// activates the focused element with Enter or Space, and removes the
+    /// focus with Escape
+    fn update_focus(&mut self) {
+        let direction = self.navigation_direction();
+        let keyboard = &self.input_state.keyboard;
+        let is_pressed = |key: RlayKeyboardKey| keyboard.keys_pressed.contains(&key);
+
+        self.activated = None;
+        // The focused element is gone, or isn't focusable anymore
+        if self.focused.as_ref().is_some_and(|focused| {
+            !self
+                .focusable
+                .iter()
+                .any(|focusable| &focusable.id == focused)
+        }) {
+            self.focused = None;
+        }
+
+        if is_pressed(RlayKeyboardKey::KEY_ESCAPE) {
+            self.focused = None;
+        } else if is_pressed(RlayKeyboardKey::KEY_TAB) && !self.focusable.is_empty() {
+            let nb_focusable = self.focusable.len();
+            let current = self.focused.as_ref().and_then(|focused| {
+                self.focusable
+                    .iter()
+                    .position(|focusable| &focusable.id == focused)
+            });
+            let next = match (current, keyboard.shift_down) {
+                (Some(idx), false) => (idx + 1) % nb_focusable,
+                (Some(idx), true) => (idx + nb_focusable - 1) % nb_focusable,
+                (None, false) => 0,
+                (None, true) => nb_focusable - 1,
+            };
+            self.focused = Some(self.focusable[next].id.clone());
+        } else if let Some(direction) = direction {
+            self.move_focus(direction);
+        } else if is_pressed(RlayKeyboardKey::KEY_ENTER) || is_pressed(RlayKeyboardKey::KEY_SPACE) {
+            self.activated = self.focused.clone();
+        }
+    }
+
+    fn navigation_direction(&self) -> Option<Direction> {
+        let keys = self.navigation_keys.as_ref()?;
+        let keys_pressed = &self.input_state.keyboard.keys_pressed;
+        let is_pressed =
+            |keys: &[RlayKeyboardKey]| keys.iter().any(|key| keys_pressed.contains(key));
+
+        [
+            (&keys.up, Direction::Up),
+            (&keys.down, Direction::Down),
+            (&keys.left, Direction::Left),
+            (&keys.right, Direction::Right),
+        ]
+        .into_iter()
+        .find(|(keys, _)| is_pressed(keys))
+        .map(|(_, direction)| direction)
+    }
+
+    /// Moves the focus to the nearest focusable element in the direction, in
+    /// the focus group of the focused element
+    fn move_focus(&mut self, direction: Direction) {
+        let current = self.focused.as_ref().and_then(|focused| {
+            self.focusable
+                .iter()
+                .find(|focusable| &focusable.id == focused)
+        });
+        let Some(current) = current else {
+            self.focused = self.focusable.first().map(|focusable| focusable.id.clone());
+            return;
+        };
+
+        let from = current.rect.center();
+        let candidates = self.focusable.iter().filter(|focusable| {
+            focusable.id != current.id && self.is_in_group(focusable.group, current.group)
+        });
+        // Being off to the side counts more than being far, so the focus stays
+        // on the same row or column
+        let distance = |focusable: &Focusable| {
+            let (along, across) = direction.split(focusable.rect.center() - from);
+            along + 2.0 * across.abs()
+        };
+
+        let nearest = candidates
+            .clone()
+            .filter(|focusable| direction.split(focusable.rect.center() - from).0 > 0.0)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+        let wraps = current
+            .group
+            .is_some_and(|group| self.focus_groups[group].0 == FocusGroup::Wrap);
+        // Without any element ahead, every element is behind, so the nearest
+        // one is the farthest back
+        let next = match nearest {
+            Some(nearest) => Some(nearest),
+            None if wraps => candidates.min_by(|a, b| distance(a).total_cmp(&distance(b))),
+            None => None,
+        };
+
+        if let Some(next) = next {
+            self.focused = Some(next.id.clone());
+        }
+    }
+
+    /// Whether the element in `element_group` is in `group`, directly or in
+    /// one of its nested groups. Every element is in `None`, the whole tree.
+    fn is_in_group(&self, element_group: Option<usize>, group: Option<usize>) -> bool {
+        let Some(group) = group else {
+            return true;
+        };
+
+        let mut element_group = element_group;
+        while let Some(current) = element_group {
+            if current == group {
+                return true;
+            }
+            element_group = self.focus_groups[current].1;
+        }
+        false
     }
 
-    fn _update_hovered_elements(&mut self, element: &ElementLayout<Done>) {
-        match element.data() {
-            &Element::Container(ref container) => {
-                let config = container.config();
-                if config.focusable {
-                    self.focusable.insert(element.data().id().to_owned());
+    /// `cursor` is where the cursor is in the layout of the parent of the
+    /// element, `visible` whether it is in the area the element is visible
+    /// in, when it is inside of a scroll container, and `layer` what the
+    /// element inherits from its parent
+    fn hit_test(
+        &mut self,
+        element: &ElementLayout<Done>,
+        cursor: Point2D,
+        visible: bool,
+        mut layer: HitLayer,
+        hit_test: &mut HitTest,
+    ) {
+        let id = element.data().id();
+        if let Element::Container(container) = element.data() {
+            let config = container.config();
+            if let Some(focus_group) = config.focus_group {
+                hit_test.focus_groups.push((focus_group, layer.focus_group));
+                layer.focus_group = Some(hit_test.focus_groups.len() - 1);
+            }
+            if config.focusable {
+                hit_test.focusable.push(Focusable {
+                    id: id.to_owned(),
+                    tab_index: config.tab_index,
+                    rect: Rect::of(element),
+                    group: layer.focus_group,
+                });
+            }
+            if config.scroll.is_enabled() {
+                self.scrollable.insert(id.to_owned());
+            }
+
+            let passthrough = config.pointer_capture == PointerCaptureMode::Passthrough;
+            if let Some(floating) = &config.floating {
+                hit_test.nb_layers += 1;
+                layer.order = LayerOrder {
+                    floating: true,
+                    z_index: floating.z_index,
+                    index: hit_test.nb_layers,
+                };
+                layer.captures = !passthrough;
+            }
+            layer.passthrough |= passthrough;
+        }
+
+        hit_test.nb_elements += 1;
+        let cursor = local_cursor(element, cursor);
+        if visible && is_cursor_inside_rect(cursor, element) {
+            hit_test.hits.push(Hit {
+                id: id.to_owned(),
+                order: (layer.order, hit_test.nb_elements),
+                // A text is part of its parent for the pointer
+                target: !layer.passthrough && !matches!(element.data(), Element::Text(_)),
+                captures: layer.captures,
+            });
+        }
+
+        // Children are visited even when the cursor is outside of the element,
+        // floating children are not bound to the rect of their parent
+        for child in element.children() {
+            self.parents
+                .insert(child.data().id().to_owned(), id.to_owned());
+            let child_visible = match child.data() {
+                Element::Container(container) if container.config().floating.is_some() => true,
+                _ => children_visible(element, cursor, visible),
+            };
+            self.hit_test(child, cursor, child_visible, layer, hit_test);
+        }
+    }
+
+    /// Queues the events of the frame, from the elements hovered on the last
+    /// frame and the input state
+    fn queue_events(&mut self, root: &ElementLayout<Done>, previous_hovered: HashSet<String>) {
+        let MouseInput {
+            mouse_position: position,
+            left_button,
+            right_button,
+            middle_button,
+            mouse_wheel,
+            ..
+        } = self.input_state.mouse;
+        let mut events = vec![];
+
+        let mut left: Vec<_> = previous_hovered.difference(&self.hovered).collect();
+        left.sort();
+        for id in left {
+            events.push(Event::new(EventKind::PointerLeave, id.clone(), position));
+        }
+        let mut entered: Vec<_> = self.hovered.difference(&previous_hovered).collect();
+        entered.sort();
+        for id in entered {
+            events.push(Event::new(EventKind::PointerEnter, id.clone(), position));
+        }
+
+        if self.focused != self.last_focused {
+            if let Some(id) = self.last_focused.take() {
+                events.push(Event::new(EventKind::Blur, id, position));
+            }
+            if let Some(id) = &self.focused {
+                events.push(Event::new(EventKind::Focus, id.clone(), position));
+            }
+            self.last_focused = self.focused.clone();
+        }
+
+        if let Some(target) = self.pointer_target.clone() {
+            let buttons = [
+                (MouseButton::Left, left_button),
+                (MouseButton::Right, right_button),
+                (MouseButton::Middle, middle_button),
+            ];
+            for (button, button_state) in buttons {
+                let kind = match button_state {
+                    MouseButtonState::Pressed => EventKind::PointerDown(button),
+                    MouseButtonState::Released => EventKind::PointerUp(button),
+                    _ => continue,
+                };
+                events.push(Event::new(kind, target.clone(), position));
+            }
+
+            if left_button == MouseButtonState::Pressed {
+                self.pressed_path = self.path(&target);
+            }
+            if left_button == MouseButtonState::Released {
+                let pressed_path = std::mem::take(&mut self.pressed_path);
+                let clicked = self
+                    .path(&target)
+                    .into_iter()
+                    .find(|id| pressed_path.contains(id));
+
+                if let Some(clicked) = clicked {
+                    events.push(Event::new(EventKind::Click, clicked.clone(), position));
+
+                    let time = self.input_state.time;
+                    match self.last_click.take() {
+                        Some((id, last_time))
+                            if id == clicked && time - last_time <= DOUBLE_CLICK_TIME =>
+                        {
+                            events.push(Event::new(EventKind::DoubleClick, clicked, position));
+                        }
+                        _ => self.last_click = Some((clicked, time)),
+                    }
                 }
             }
-            _ => {}
+
+            if right_button == MouseButtonState::Released {
+                events.push(Event::new(EventKind::RightClick, target.clone(), position));
+            }
+            if mouse_wheel != Point2D::default() {
+                events.push(Event::new(EventKind::Wheel(mouse_wheel), target, position));
+            }
+        }
+
+        if let Some(activated) = self.activated.clone() {
+            events.push(Event::new(EventKind::Click, activated, position));
         }
-        if is_cursor_inside_rect(self.input_state.mouse.mouse_position, element) {
-            // if !self.hovered.is_empty() {
-            //     return;
-            // }
-            self.hovered.insert(element.data().id().to_string());
 
-            for child in element.children() {
-                self._update_hovered_elements(child);
+        let key_target = self
+            .focused
+            .clone()
+            .unwrap_or_else(|| root.data().id().to_owned());
+        let keyboard = &self.input_state.keyboard;
+        let pressed = sorted_keys(&keyboard.keys_pressed).map(EventKind::Key);
+        let repeated = sorted_keys(&keyboard.keys_repeated).map(EventKind::KeyRepeat);
+        for kind in pressed.chain(repeated) {
+            events.push(Event::new(kind, key_target.clone(), position));
+        }
+
+        self.events = events;
+    }
+
+    /// The ids of the element and of its ancestors, from the element to the root
+    pub fn path(&self, element_id: &str) -> Vec<String> {
+        let mut path = vec![element_id.to_owned()];
+        while let Some(parent) = self.parents.get(path.last().unwrap()) {
+            path.push(parent.clone());
+        }
+        path
+    }
+
+    /// The events of the last frame, in the order they were dispatched
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The events of the last frame aimed at the element
+    pub fn events_of<'a>(&'a self, element_id: &'a str) -> impl Iterator<Item = &'a Event> {
+        self.events
+            .iter()
+            .filter(move |event| event.target == element_id)
+    }
+
+    /// Scrolls the scroll container under the cursor with the mouse wheel and
+    /// keeps every scroll offset inside of the content of its container. The
+    /// offsets of the containers that are gone are dropped.
+    pub(crate) fn update_scroll_offsets(&mut self, element: &ElementLayout<Done>) {
+        self.scroll_offsets
+            .retain(|id, _| self.scrollable.contains(id));
+        // The children were laid out with the offsets from before the wheel
+        let laid_out = self.scroll_offsets.clone();
+
+        let wheel = self.input_state.mouse.mouse_wheel;
+        if wheel != Point2D::default() {
+            let target = self
+                .pointer_over
+                .iter()
+                .find(|id| self.scrollable.contains(*id));
+            if let Some(id) = target {
+                let offset = self.scroll_offset(id) + wheel * Point2D::scalar(SCROLL_SPEED);
+                self.scroll_offsets.insert(id.to_owned(), offset);
             }
         }
+
+        self.clamp_scroll_offsets(element, &laid_out);
+    }
+
+    fn clamp_scroll_offsets(
+        &mut self,
+        element: &ElementLayout<Done>,
+        laid_out: &HashMap<String, Point2D>,
+    ) {
+        if let Element::Container(container) = element.data()
+            && container.config().scroll.is_enabled()
+            && let Some(offset) = self.scroll_offsets.get_mut(container.id())
+        {
+            let scroll = container.config().scroll;
+            let laid_out = laid_out.get(container.id()).copied().unwrap_or_default();
+            let max_scroll = max_scroll_offset(element, scroll.restrict(laid_out));
+            let clamped = scroll.restrict(Point2D::new(
+                offset.x.clamp(-max_scroll.x, 0.0),
+                offset.y.clamp(-max_scroll.y, 0.0),
+            ));
+            *offset = clamped;
+        }
+
+        for child in element.children() {
+            self.clamp_scroll_offsets(child, laid_out);
+        }
+    }
+
+    pub fn scroll_offset(&self, element_id: &str) -> Point2D {
+        self.scroll_offsets
+            .get(element_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_scroll_offset(&mut self, element_id: &str, offset: Point2D) {
+        self.scroll_offsets.insert(element_id.to_owned(), offset);
     }
 
     pub fn is_hovered(&self, element_id: &str) -> bool {
         self.hovered.contains(element_id)
     }
 
+    /// The elements under the cursor, the topmost first. The elements under a
+    /// floating element are left out, unless it lets the pointer through.
+    pub fn pointer_over_ids(&self) -> &[String] {
+        &self.pointer_over
+    }
+
+    /// The topmost element under the cursor that catches the pointer. The
+    /// clicks go to it, and the events about the pointer are aimed at it.
+    pub fn pointer_target(&self) -> Option<&str> {
+        self.pointer_target.as_deref()
+    }
+
     pub fn is_clicked(&self, element_id: &str) -> bool {
         self.get_element_state(element_id)
             .map(|state| state.is_clicked)
@@ -181,6 +604,17 @@ impl AppState {
             .unwrap_or(false)
     }
 
+    /// The focusable elements of the last frame, in the order of Tab
+    pub fn focus_order(&self) -> impl Iterator<Item = &str> {
+        self.focusable.iter().map(|focusable| focusable.id.as_str())
+    }
+
+    /// Moves the focus with the navigation keys, to the nearest focusable
+    /// element in their direction. It is disabled with `None`, the default.
+    pub fn set_navigation_keys(&mut self, navigation_keys: Option<NavigationKeys>) {
+        self.navigation_keys = navigation_keys;
+    }
+
     pub fn is_focused(&self, element_id: &str) -> bool {
         self.focused.as_ref().is_some_and(|id| id == element_id)
     }
@@ -259,10 +693,183 @@ impl AppState {
 }
 
 fn is_cursor_inside_rect(cursor: Point2D, element: &ElementLayout<Done>) -> bool {
-    cursor.x >= element.position().x
-        && cursor.x <= element.position().x + element.dimensions().width
-        && cursor.y >= element.position().y
-        && cursor.y <= element.position().y + element.dimensions().height
+    Rect::of(element).contains(cursor)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    position: Point2D,
+    dimensions: Dimension2D,
+}
+
+impl Rect {
+    fn of(element: &ElementLayout<Done>) -> Self {
+        Self {
+            position: element.position(),
+            dimensions: element.dimensions(),
+        }
+    }
+
+    fn contains(&self, point: Point2D) -> bool {
+        point.x >= self.position.x
+            && point.x <= self.position.x + self.dimensions.width
+            && point.y >= self.position.y
+            && point.y <= self.position.y + self.dimensions.height
+    }
+
+    fn center(&self) -> Point2D {
+        self.position + Point2D::from(self.dimensions) / Point2D::scalar(2.0)
+    }
+}
+
+struct Focusable {
+    id: String,
+    tab_index: Option<u16>,
+    rect: Rect,
+    /// The innermost focus group the element is in
+    group: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// How far the offset goes in the direction, and how far it goes across it
+    fn split(self, offset: Point2D) -> (f32, f32) {
+        match self {
+            Self::Up => (-offset.y, offset.x),
+            Self::Down => (offset.y, offset.x),
+            Self::Left => (-offset.x, offset.y),
+            Self::Right => (offset.x, offset.y),
+        }
+    }
+}
+
+/// The floating elements are drawn over the rest of the tree, by z-index,
+/// then in the order of the tree
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct LayerOrder {
+    floating: bool,
+    z_index: u16,
+    index: usize,
+}
+
+/// What the children of an element inherit for the hit test
+#[derive(Debug, Default, Clone, Copy)]
+struct HitLayer {
+    order: LayerOrder,
+    /// The elements of the layers under this one are not under the cursor
+    /// when it is over an element of this layer
+    captures: bool,
+    /// The elements let the pointer through to the ones under them
+    passthrough: bool,
+    /// The innermost focus group the element is in
+    focus_group: Option<usize>,
+}
+
+struct Hit {
+    id: String,
+    /// The layer of the element, then its position in the tree. The elements
+    /// drawn last are on top.
+    order: (LayerOrder, usize),
+    target: bool,
+    captures: bool,
+}
+
+#[derive(Default)]
+struct HitTest {
+    hits: Vec<Hit>,
+    /// The focusable elements, in the order of the tree
+    focusable: Vec<Focusable>,
+    focus_groups: Vec<(FocusGroup, Option<usize>)>,
+    nb_elements: usize,
+    nb_layers: usize,
+}
+
+impl HitTest {
+    /// The elements under the cursor, the topmost first, down to the first
+    /// layer capturing the pointer
+    fn pointer_over(mut self) -> Vec<Hit> {
+        self.hits.sort_by_key(|hit| Reverse(hit.order));
+
+        let mut captured = None;
+        self.hits
+            .into_iter()
+            .take_while(|hit| {
+                if captured.is_some_and(|layer| hit.order.0 < layer) {
+                    return false;
+                }
+                if hit.captures {
+                    captured = Some(hit.order.0);
+                }
+                true
+            })
+            .collect()
+    }
+}
+
+fn sorted_keys(keys: &HashSet<RlayKeyboardKey>) -> impl Iterator<Item = RlayKeyboardKey> {
+    let mut keys: Vec<_> = keys.iter().copied().collect();
+    keys.sort_by_key(|key| *key as u32);
+    keys.into_iter()
+}
+
+/// Where the cursor is in the layout of the element, undoing its transform
+fn local_cursor(element: &ElementLayout<Done>, cursor: Point2D) -> Point2D {
+    match element.data() {
+        Element::Container(container) => container.config().transform.map_or(cursor, |transform| {
+            let pivot = transform.pivot(element.position(), element.dimensions());
+            transform.invert(cursor, pivot)
+        }),
+        _ => cursor,
+    }
+}
+
+/// Whether the children of the element are visible under the cursor, which
+/// they are not outside of a scroll container
+fn children_visible(element: &ElementLayout<Done>, cursor: Point2D, visible: bool) -> bool {
+    match element.data() {
+        Element::Container(container) if container.config().scroll.is_enabled() => {
+            visible && is_cursor_inside_rect(cursor, element)
+        }
+        _ => visible,
+    }
+}
+
+/// How far the content of the element can be scrolled on each axis. The
+/// children are already moved by `offset`, the current scroll offset.
+fn max_scroll_offset(element: &ElementLayout<Done>, offset: Point2D) -> Point2D {
+    let Element::Container(container) = element.data() else {
+        return Point2D::default();
+    };
+    let padding = container.config().content_padding();
+
+    let content_end = element
+        .children()
+        .iter()
+        .filter(|child| match child.data() {
+            Element::Container(container) => container.config().floating.is_none(),
+            _ => true,
+        })
+        .map(|child| child.position() + Point2D::from(child.dimensions()))
+        .reduce(|a, b| Point2D::new(a.x.max(b.x), a.y.max(b.y)));
+
+    let Some(content_end) = content_end else {
+        return Point2D::default();
+    };
+
+    let content_dimensions = content_end - offset - element.position()
+        + Point2D::new(padding.right as f32, padding.bottom as f32);
+
+    Point2D::new(
+        (content_dimensions.x - element.dimensions().width).max(0.0),
+        (content_dimensions.y - element.dimensions().height).max(0.0),
+    )
 }
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
@@ -281,15 +888,24 @@ pub struct MouseInput {
     pub left_button: MouseButtonState,
     pub right_button: MouseButtonState,
     pub middle_button: MouseButtonState,
+    /// The movement of the wheel since the last frame, positive when it is
+    /// scrolled up or to the left
+    pub mouse_wheel: Point2D,
 }
 
 #[derive(Default)]
 pub struct KeyboardInput {
-    // pub keys_down: HashSet<u16>,
-    // pub keys_released: HashSet<u16>,
-    pub keys_pressed: HashSet<u16>,
-    pub last_char_pressed: Option<char>,
-    pub last_key_pressed: Option<u16>,
+    /// The keys held down
+    pub keys_down: HashSet<RlayKeyboardKey>,
+    /// The keys pressed this frame
+    pub keys_pressed: HashSet<RlayKeyboardKey>,
+    /// The keys released this frame
+    pub keys_released: HashSet<RlayKeyboardKey>,
+    /// The keys held down long enough to be repeated this frame, like when
+    /// typing
+    pub keys_repeated: HashSet<RlayKeyboardKey>,
+    /// Every character typed this frame, in order
+    pub chars_pressed: Vec<char>,
 
     pub shift_down: bool,
     pub ctrl_down: bool,
@@ -297,13 +913,37 @@ pub struct KeyboardInput {
     pub super_down: bool,
 }
 
+/// The keys moving the focus to the nearest focusable element in their
+/// direction, e.g. the arrows or the keys a D-pad is mapped to
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationKeys {
+    pub up: Vec<RlayKeyboardKey>,
+    pub down: Vec<RlayKeyboardKey>,
+    pub left: Vec<RlayKeyboardKey>,
+    pub right: Vec<RlayKeyboardKey>,
+}
+
+/// The arrows
+impl Default for NavigationKeys {
+    fn default() -> Self {
+        Self {
+            up: vec![RlayKeyboardKey::KEY_UP],
+            down: vec![RlayKeyboardKey::KEY_DOWN],
+            left: vec![RlayKeyboardKey::KEY_LEFT],
+            right: vec![RlayKeyboardKey::KEY_RIGHT],
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct InputState {
     pub mouse: MouseInput,
     pub keyboard: KeyboardInput,
+    /// The number of seconds since the start of the app, used to detect
+    /// double clicks
+    pub time: f64,
 }
 
-
 #[macro_export]
 macro_rules! map {
     ($($key:literal : $val:expr),* $(,)?) => {{
@@ -430,3 +1070,840 @@ pub enum RlayKeyboardKey {
     KEY_VOLUME_UP = 24,
     KEY_VOLUME_DOWN = 25,
 }
+
+impl RlayKeyboardKey {
+    pub const ALL: [Self; 110] = [
+        Self::KEY_NULL,
+        Self::KEY_APOSTROPHE,
+        Self::KEY_COMMA,
+        Self::KEY_MINUS,
+        Self::KEY_PERIOD,
+        Self::KEY_SLASH,
+        Self::KEY_ZERO,
+        Self::KEY_ONE,
+        Self::KEY_TWO,
+        Self::KEY_THREE,
+        Self::KEY_FOUR,
+        Self::KEY_FIVE,
+        Self::KEY_SIX,
+        Self::KEY_SEVEN,
+        Self::KEY_EIGHT,
+        Self::KEY_NINE,
+        Self::KEY_SEMICOLON,
+        Self::KEY_EQUAL,
+        Self::KEY_A,
+        Self::KEY_B,
+        Self::KEY_C,
+        Self::KEY_D,
+        Self::KEY_E,
+        Self::KEY_F,
+        Self::KEY_G,
+        Self::KEY_H,
+        Self::KEY_I,
+        Self::KEY_J,
+        Self::KEY_K,
+        Self::KEY_L,
+        Self::KEY_M,
+        Self::KEY_N,
+        Self::KEY_O,
+        Self::KEY_P,
+        Self::KEY_Q,
+        Self::KEY_R,
+        Self::KEY_S,
+        Self::KEY_T,
+        Self::KEY_U,
+        Self::KEY_V,
+        Self::KEY_W,
+        Self::KEY_X,
+        Self::KEY_Y,
+        Self::KEY_Z,
+        Self::KEY_LEFT_BRACKET,
+        Self::KEY_BACKSLASH,
+        Self::KEY_RIGHT_BRACKET,
+        Self::KEY_GRAVE,
+        Self::KEY_SPACE,
+        Self::KEY_ESCAPE,
+        Self::KEY_ENTER,
+        Self::KEY_TAB,
+        Self::KEY_BACKSPACE,
+        Self::KEY_INSERT,
+        Self::KEY_DELETE,
+        Self::KEY_RIGHT,
+        Self::KEY_LEFT,
+        Self::KEY_DOWN,
+        Self::KEY_UP,
+        Self::KEY_PAGE_UP,
+        Self::KEY_PAGE_DOWN,
+        Self::KEY_HOME,
+        Self::KEY_END,
+        Self::KEY_CAPS_LOCK,
+        Self::KEY_SCROLL_LOCK,
+        Self::KEY_NUM_LOCK,
+        Self::KEY_PRINT_SCREEN,
+        Self::KEY_PAUSE,
+        Self::KEY_F1,
+        Self::KEY_F2,
+        Self::KEY_F3,
+        Self::KEY_F4,
+        Self::KEY_F5,
+        Self::KEY_F6,
+        Self::KEY_F7,
+        Self::KEY_F8,
+        Self::KEY_F9,
+        Self::KEY_F10,
+        Self::KEY_F11,
+        Self::KEY_F12,
+        Self::KEY_LEFT_SHIFT,
+        Self::KEY_LEFT_CONTROL,
+        Self::KEY_LEFT_ALT,
+        Self::KEY_LEFT_SUPER,
+        Self::KEY_RIGHT_SHIFT,
+        Self::KEY_RIGHT_CONTROL,
+        Self::KEY_RIGHT_ALT,
+        Self::KEY_RIGHT_SUPER,
+        Self::KEY_KB_MENU,
+        Self::KEY_KP_0,
+        Self::KEY_KP_1,
+        Self::KEY_KP_2,
+        Self::KEY_KP_3,
+        Self::KEY_KP_4,
+        Self::KEY_KP_5,
+        Self::KEY_KP_6,
+        Self::KEY_KP_7,
+        Self::KEY_KP_8,
+        Self::KEY_KP_9,
+        Self::KEY_KP_DECIMAL,
+        Self::KEY_KP_DIVIDE,
+        Self::KEY_KP_MULTIPLY,
+        Self::KEY_KP_SUBTRACT,
+        Self::KEY_KP_ADD,
+        Self::KEY_KP_ENTER,
+        Self::KEY_KP_EQUAL,
+        Self::KEY_BACK,
+        Self::KEY_MENU,
+        Self::KEY_VOLUME_UP,
+        Self::KEY_VOLUME_DOWN,
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AppCtx, AppCtxUtils, ContainerConfig, FloatingConfig, LayoutDirection, Padding,
+        ScrollConfig, Sizing, SizingAxis, TextDimensions, Transform, err::RlayError,
+    };
+
+    /// An element of the tree laid out by [`layout`]
+    struct Node {
+        id: &'static str,
+        config: ContainerConfig,
+        children: Vec<Node>,
+    }
+
+    fn node(id: &'static str, config: ContainerConfig, children: Vec<Node>) -> Node {
+        Node {
+            id,
+            config,
+            children,
+        }
+    }
+
+    /// A focusable square of 10 by 10 pixels
+    fn button(id: &'static str) -> Node {
+        let config = ContainerConfig {
+            sizing: Sizing::new(SizingAxis::Fixed(10.0), SizingAxis::Fixed(10.0)),
+            focusable: true,
+            ..Default::default()
+        };
+        node(id, config, vec![])
+    }
+
+    /// A context measuring every text as empty
+    fn ctx() -> AppCtx {
+        AppCtx::new(AppCtxUtils::new(|_, _, _| TextDimensions {
+            width: 0.0,
+            height: 0.0,
+            offset_y: 0.0,
+        }))
+    }
+
+    fn layout(root: Node) -> ElementLayout<Done> {
+        layout_in(&mut ctx(), root)
+    }
+
+    /// Lays the tree out with the state of the context, e.g. its scroll
+    /// offsets
+    fn layout_in(ctx: &mut AppCtx, root: Node) -> ElementLayout<Done> {
+        fn open(ctx: &mut AppCtx, node: Node) {
+            ctx.open_element(Element::container(node.config, node.id.to_string()));
+            for child in node.children {
+                open(ctx, child);
+            }
+            ctx.close_element();
+        }
+
+        ctx.clear();
+        open(ctx, root);
+        let root = ctx.try_into().unwrap();
+        crate::calculate_layout(ctx, root).unwrap()
+    }
+
+    /// The input of a frame where the keys are pressed, and the cursor is
+    /// left at (0, 0)
+    fn keys(keys_pressed: &[RlayKeyboardKey]) -> InputState {
+        InputState {
+            keyboard: KeyboardInput {
+                keys_pressed: keys_pressed.iter().copied().collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// The input of a frame where the cursor is at (x, y), `time` seconds
+    /// after the start
+    fn pointer(x: f32, y: f32, left_button: MouseButtonState, time: f64) -> InputState {
+        InputState {
+            mouse: MouseInput {
+                mouse_position: Point2D::new(x, y),
+                left_button,
+                ..Default::default()
+            },
+            time,
+            ..Default::default()
+        }
+    }
+
+    /// The buttons `a` and `b` side by side in `row`, 10 pixels from the top
+    /// left corner of the root and from each other
+    fn two_buttons() -> ElementLayout<Done> {
+        let root = ContainerConfig {
+            padding: Padding::default().all(10),
+            ..Default::default()
+        };
+        let row = ContainerConfig {
+            child_gap: 10,
+            ..Default::default()
+        };
+        layout(node(
+            "root",
+            root,
+            vec![node("row", row, vec![button("a"), button("b")])],
+        ))
+    }
+
+    fn frame(state: &mut AppState, layout: &ElementLayout<Done>, input_state: InputState) {
+        state.set_input_state(input_state);
+        state.update_hovered_elements(layout);
+        state.update_scroll_offsets(layout);
+    }
+
+    /// The ids of the element and of its descendants, parents first
+    fn ids(element: &ElementLayout<Done>) -> Vec<String> {
+        let children = element.children().iter().flat_map(ids);
+        std::iter::once(element.data().id().to_owned())
+            .chain(children)
+            .collect()
+    }
+
+    fn events(state: &AppState) -> Vec<(EventKind, &str)> {
+        state
+            .events()
+            .iter()
+            .map(|event| (event.kind, event.target.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn move_the_focus_with_the_keyboard_before_the_cursor_moves() {
+        let not_focusable = node("c", ContainerConfig::default(), vec![]);
+        let layout = layout(node(
+            "root",
+            ContainerConfig::default(),
+            vec![button("a"), button("b"), not_focusable],
+        ));
+        let mut state = AppState::new();
+
+        frame(&mut state, &layout, keys(&[RlayKeyboardKey::KEY_TAB]));
+        assert!(state.is_focused("a"));
+        assert_eq!(
+            events(&state),
+            [
+                (EventKind::Focus, "a"),
+                (EventKind::Key(RlayKeyboardKey::KEY_TAB), "a")
+            ]
+        );
+        assert_eq!(state.pointer_target(), None);
+
+        frame(&mut state, &layout, keys(&[RlayKeyboardKey::KEY_TAB]));
+        assert!(state.is_focused("b"));
+        // Past the last focusable element, the focus goes back to the first
+        frame(&mut state, &layout, keys(&[RlayKeyboardKey::KEY_TAB]));
+        assert!(state.is_focused("a"));
+
+        let shift_tab = InputState {
+            keyboard: KeyboardInput {
+                shift_down: true,
+                ..keys(&[RlayKeyboardKey::KEY_TAB]).keyboard
+            },
+            ..Default::default()
+        };
+        frame(&mut state, &layout, shift_tab);
+        assert!(state.is_focused("b"));
+
+        frame(&mut state, &layout, keys(&[RlayKeyboardKey::KEY_ENTER]));
+        assert!(state.is_clicked("b"));
+        assert!(events(&state).contains(&(EventKind::Click, "b")));
+        frame(&mut state, &layout, keys(&[]));
+        assert!(!state.is_clicked("b"));
+
+        frame(&mut state, &layout, keys(&[RlayKeyboardKey::KEY_ESCAPE]));
+        assert!(!state.is_focused("b"));
+        // Without a focused element, the keys go to the root
+        assert_eq!(
+            events(&state),
+            [
+                (EventKind::Blur, "b"),
+                (EventKind::Key(RlayKeyboardKey::KEY_ESCAPE), "root")
+            ]
+        );
+    }
+
+    #[test]
+    fn navigate_the_focus_groups_with_the_arrows_before_the_cursor_moves() {
+        use RlayKeyboardKey::{KEY_LEFT, KEY_RIGHT, KEY_UP};
+
+        let group = |id, focus_group, children| {
+            let config = ContainerConfig {
+                focus_group: Some(focus_group),
+                ..Default::default()
+            };
+            node(id, config, children)
+        };
+        let layout = layout(node(
+            "root",
+            ContainerConfig::default(),
+            vec![
+                button("a"),
+                group("wrap", FocusGroup::Wrap, vec![button("b"), button("c")]),
+                group("trap", FocusGroup::Trap, vec![button("d"), button("e")]),
+            ],
+        ));
+        let mut state = AppState::new();
+        state.set_navigation_keys(Some(NavigationKeys::default()));
+        let press = |state: &mut AppState, key| {
+            frame(state, &layout, keys(&[key]));
+            state.focused.clone()
+        };
+
+        // Without a focused element, the first one is focused
+        assert_eq!(press(&mut state, KEY_RIGHT).as_deref(), Some("a"));
+        assert_eq!(press(&mut state, KEY_RIGHT).as_deref(), Some("b"));
+        assert_eq!(press(&mut state, KEY_RIGHT).as_deref(), Some("c"));
+        assert_eq!(press(&mut state, KEY_RIGHT).as_deref(), Some("b"));
+        assert_eq!(press(&mut state, KEY_LEFT).as_deref(), Some("c"));
+
+        state.set_focused(Some("d".to_string()));
+        assert_eq!(press(&mut state, KEY_RIGHT).as_deref(), Some("e"));
+        assert_eq!(press(&mut state, KEY_RIGHT).as_deref(), Some("e"));
+        assert_eq!(press(&mut state, KEY_LEFT).as_deref(), Some("d"));
+        assert_eq!(press(&mut state, KEY_LEFT).as_deref(), Some("d"));
+        assert_eq!(press(&mut state, KEY_UP).as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn follow_a_key_from_its_press_to_its_release() {
+        use RlayKeyboardKey::{KEY_A, KEY_B};
+
+        let layout = layout(node("root", ContainerConfig::default(), vec![button("a")]));
+        let mut ctx = ctx();
+        let frame = |ctx: &mut AppCtx, keyboard| {
+            ctx.set_input_state(InputState {
+                keyboard,
+                ..Default::default()
+            });
+            ctx.update_hovered_elements(&layout);
+        };
+        let set = |keys: &[RlayKeyboardKey]| keys.iter().copied().collect();
+
+        frame(&mut ctx, keys(&[RlayKeyboardKey::KEY_TAB]).keyboard);
+        assert!(ctx.state().is_focused("a"));
+
+        frame(
+            &mut ctx,
+            KeyboardInput {
+                keys_down: set(&[KEY_B, KEY_A]),
+                keys_pressed: set(&[KEY_B, KEY_A]),
+                chars_pressed: vec!['b', 'a', 'B'],
+                ..Default::default()
+            },
+        );
+        assert!(ctx.is_key_down(KEY_A) && ctx.is_key_pressed(KEY_A));
+        assert!(ctx.is_key_pressed_repeat(KEY_A) && !ctx.is_key_released(KEY_A));
+        // The characters keep the order they were typed in
+        assert_eq!(ctx.chars_pressed(), ['b', 'a', 'B']);
+        // The keys go to the focused element, in the order of their codes
+        assert_eq!(
+            events(ctx.state()),
+            [(EventKind::Key(KEY_A), "a"), (EventKind::Key(KEY_B), "a")]
+        );
+
+        frame(
+            &mut ctx,
+            KeyboardInput {
+                keys_down: set(&[KEY_A]),
+                keys_released: set(&[KEY_B]),
+                ..Default::default()
+            },
+        );
+        assert!(ctx.is_key_down(KEY_A) && !ctx.is_key_pressed(KEY_A));
+        assert!(!ctx.is_key_pressed_repeat(KEY_A));
+        assert!(!ctx.is_key_down(KEY_B) && ctx.is_key_released(KEY_B));
+        assert!(ctx.chars_pressed().is_empty());
+        assert!(events(ctx.state()).is_empty());
+
+        frame(
+            &mut ctx,
+            KeyboardInput {
+                keys_down: set(&[KEY_A]),
+                keys_repeated: set(&[KEY_A]),
+                chars_pressed: vec!['a'],
+                ..Default::default()
+            },
+        );
+        assert!(ctx.is_key_down(KEY_A) && !ctx.is_key_pressed(KEY_A));
+        assert!(ctx.is_key_pressed_repeat(KEY_A));
+        assert_eq!(ctx.chars_pressed(), ['a']);
+        assert_eq!(events(ctx.state()), [(EventKind::KeyRepeat(KEY_A), "a")]);
+
+        frame(
+            &mut ctx,
+            KeyboardInput {
+                keys_released: set(&[KEY_A]),
+                ..Default::default()
+            },
+        );
+        assert!(!ctx.is_key_down(KEY_A) && !ctx.is_key_pressed_repeat(KEY_A));
+        assert!(ctx.is_key_released(KEY_A) && !ctx.is_key_released(KEY_B));
+        assert!(events(ctx.state()).is_empty());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn read_the_pressed_keys_from_the_deprecated_util_too() {
+        let mut utils = AppCtxUtils::new(|_, _, _| TextDimensions {
+            width: 0.0,
+            height: 0.0,
+            offset_y: 0.0,
+        });
+        utils.is_key_pressed = Box::new(|key| key == RlayKeyboardKey::KEY_A);
+        let mut ctx = AppCtx::new(utils);
+        ctx.set_input_state(keys(&[RlayKeyboardKey::KEY_B]));
+
+        assert!(ctx.is_key_pressed(RlayKeyboardKey::KEY_A));
+        assert!(ctx.is_key_pressed(RlayKeyboardKey::KEY_B));
+        assert!(!ctx.is_key_pressed(RlayKeyboardKey::KEY_C));
+    }
+
+    #[test]
+    fn queue_the_pointer_events_and_the_clicks() {
+        use MouseButtonState::{Pressed, Released, Up};
+
+        let layout = two_buttons();
+        let mut state = AppState::new();
+
+        frame(&mut state, &layout, pointer(15.0, 15.0, Up, 0.0));
+        assert_eq!(state.pointer_target(), Some("a"));
+        assert_eq!(
+            events(&state),
+            [
+                (EventKind::PointerEnter, "a"),
+                (EventKind::PointerEnter, "root"),
+                (EventKind::PointerEnter, "row")
+            ]
+        );
+
+        frame(&mut state, &layout, pointer(15.0, 15.0, Pressed, 0.1));
+        assert_eq!(
+            events(&state),
+            [(EventKind::PointerDown(MouseButton::Left), "a")]
+        );
+        // Released over another element, the click goes to the innermost
+        // element containing both
+        frame(&mut state, &layout, pointer(35.0, 15.0, Released, 0.2));
+        assert_eq!(
+            events(&state),
+            [
+                (EventKind::PointerLeave, "a"),
+                (EventKind::PointerEnter, "b"),
+                (EventKind::PointerUp(MouseButton::Left), "b"),
+                (EventKind::Click, "row")
+            ]
+        );
+
+        let click = |state: &mut AppState, time| {
+            frame(state, &layout, pointer(35.0, 15.0, Pressed, time));
+            frame(state, &layout, pointer(35.0, 15.0, Released, time + 0.1));
+            events(state)
+                .into_iter()
+                .filter(|(kind, _)| matches!(kind, EventKind::Click | EventKind::DoubleClick))
+                .map(|(kind, id)| (kind, id.to_owned()))
+                .collect::<Vec<_>>()
+        };
+        let b = || "b".to_owned();
+        assert_eq!(click(&mut state, 1.0), [(EventKind::Click, b())]);
+        assert_eq!(
+            click(&mut state, 1.2),
+            [(EventKind::Click, b()), (EventKind::DoubleClick, b())]
+        );
+        // The next click starts a new pair, and so does one too late
+        assert_eq!(click(&mut state, 1.4), [(EventKind::Click, b())]);
+        assert_eq!(click(&mut state, 3.0), [(EventKind::Click, b())]);
+    }
+
+    #[test]
+    fn bubble_the_events_up_to_the_root_until_one_is_stopped() {
+        let layout = two_buttons();
+        let mut ctx = ctx();
+        let calls = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        for id in ["a", "row", "root"] {
+            let calls = calls.clone();
+            ctx.on_event(id, move |event, _| {
+                calls
+                    .borrow_mut()
+                    .push((event.kind, event.current_target.clone()));
+                if event.kind == EventKind::Click && event.current_target == "row" {
+                    event.stop_propagation();
+                }
+            });
+        }
+
+        ctx.set_input_state(pointer(15.0, 15.0, MouseButtonState::Pressed, 0.0));
+        ctx.update_hovered_elements(&layout);
+        ctx.dispatch_events();
+        ctx.set_input_state(pointer(15.0, 15.0, MouseButtonState::Released, 0.1));
+        ctx.update_hovered_elements(&layout);
+        ctx.dispatch_events();
+
+        let calls = calls.borrow();
+        let calls: Vec<_> = calls
+            .iter()
+            .map(|(kind, id)| (*kind, id.as_str()))
+            .collect();
+        let down = EventKind::PointerDown(MouseButton::Left);
+        let up = EventKind::PointerUp(MouseButton::Left);
+        assert_eq!(
+            calls,
+            [
+                // The cursor enters each element on its own
+                (EventKind::PointerEnter, "a"),
+                (EventKind::PointerEnter, "root"),
+                (EventKind::PointerEnter, "row"),
+                (down, "a"),
+                (down, "row"),
+                (down, "root"),
+                (up, "a"),
+                (up, "row"),
+                (up, "root"),
+                (EventKind::Click, "a"),
+                (EventKind::Click, "row")
+            ]
+        );
+    }
+
+    #[test]
+    fn hit_the_topmost_floating_element_or_the_one_under_a_passthrough() {
+        let square = |id, z_index, pointer_capture, children| {
+            let config = ContainerConfig {
+                sizing: Sizing::new(SizingAxis::Fixed(30.0), SizingAxis::Fixed(30.0)),
+                floating: Some(FloatingConfig {
+                    z_index,
+                    ..Default::default()
+                }),
+                pointer_capture,
+                ..Default::default()
+            };
+            node(id, config, children)
+        };
+        let hit = |pointer_capture| {
+            let icon = node("icon", ContainerConfig::default(), vec![button("dot")]);
+            let layout = layout(node(
+                "root",
+                ContainerConfig::default(),
+                vec![
+                    square("high", 2, pointer_capture, vec![icon]),
+                    square("low", 1, PointerCaptureMode::Capture, vec![]),
+                    button("under"),
+                ],
+            ));
+            let mut state = AppState::new();
+            frame(
+                &mut state,
+                &layout,
+                pointer(5.0, 5.0, MouseButtonState::Up, 0.0),
+            );
+            let target = state.pointer_target().map(str::to_owned);
+            (target, state.pointer_over_ids().to_vec())
+        };
+
+        // The highest z-index is on top, whatever the order of the tree, and
+        // hides the elements under it from the pointer
+        let (target, over) = hit(PointerCaptureMode::Capture);
+        assert_eq!(target.as_deref(), Some("dot"));
+        assert_eq!(over, ["dot", "icon", "high"]);
+
+        // The pointer goes through the element and its children to the next
+        // layer, but they are still hovered
+        let (target, over) = hit(PointerCaptureMode::Passthrough);
+        assert_eq!(target.as_deref(), Some("low"));
+        assert_eq!(over, ["dot", "icon", "high", "low"]);
+    }
+
+    #[test]
+    fn scroll_the_container_under_the_cursor_inside_of_its_content() {
+        let fixed =
+            |width, height| Sizing::new(SizingAxis::Fixed(width), SizingAxis::Fixed(height));
+        let row = |id| {
+            let config = ContainerConfig {
+                sizing: fixed(50.0, 20.0),
+                ..Default::default()
+            };
+            node(id, config, vec![])
+        };
+        // 100 pixels of rows in 50 pixels, with room for the cursor beside it
+        let root = |with_list| {
+            let list = ContainerConfig {
+                sizing: fixed(50.0, 50.0),
+                layout_direction: LayoutDirection::TopToBottom,
+                scroll: ScrollConfig {
+                    vertical: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let rows = ["a", "b", "c", "d", "e"].map(row).into();
+            let root = ContainerConfig {
+                sizing: fixed(100.0, 100.0),
+                ..Default::default()
+            };
+            let children = match with_list {
+                true => vec![node("list", list, rows)],
+                false => vec![],
+            };
+            node("root", root, children)
+        };
+        let mut ctx = ctx();
+        // Like a frame of the renderer, laid out with the offsets of the last
+        // one
+        let mut wheel = |with_list, (x, y), wheel| {
+            let mut input_state = pointer(x, y, MouseButtonState::Up, 0.0);
+            input_state.mouse.mouse_wheel = wheel;
+            ctx.set_input_state(input_state);
+            let layout = layout_in(&mut ctx, root(with_list));
+            ctx.update_hovered_elements(&layout);
+            ctx.update_scroll_offsets(&layout);
+            ctx.get_scroll_offset("list")
+        };
+
+        let down = Point2D::new(0.0, -1.0);
+        assert_eq!(wheel(true, (25.0, 25.0), down).y, -30.0);
+        // Past the end of the content, the offset stays at its end
+        assert_eq!(wheel(true, (25.0, 25.0), down).y, -50.0);
+        assert_eq!(wheel(true, (25.0, 25.0), down).y, -50.0);
+        // Only the axes the container scrolls on move
+        let sideways = Point2D::new(1.0, 1.0);
+        assert_eq!(
+            wheel(true, (25.0, 25.0), sideways),
+            Point2D::new(0.0, -20.0)
+        );
+        // The wheel scrolls nothing outside of the container
+        assert_eq!(wheel(true, (75.0, 75.0), down).y, -20.0);
+
+        let no_wheel = Point2D::default();
+        assert_eq!(wheel(false, (25.0, 25.0), no_wheel).y, 0.0);
+        // The offset was dropped with the container, it starts over
+        assert_eq!(wheel(true, (25.0, 25.0), no_wheel).y, 0.0);
+    }
+
+    #[test]
+    fn tab_through_the_tab_indices_first_and_drop_the_focus_of_an_element_gone() {
+        let indexed = |id, tab_index| {
+            let mut button = button(id);
+            button.config.tab_index = Some(tab_index);
+            button
+        };
+        let root = |children| node("root", ContainerConfig::default(), children);
+        let with_a = layout(root(vec![button("a"), indexed("b", 2), indexed("c", 1)]));
+        let mut state = AppState::new();
+        let mut tab = || {
+            frame(&mut state, &with_a, keys(&[RlayKeyboardKey::KEY_TAB]));
+            state.focused.clone().unwrap()
+        };
+        assert_eq!([tab(), tab(), tab(), tab()], ["c", "b", "a", "c"]);
+
+        state.set_focused(Some("a".to_string()));
+        frame(&mut state, &with_a, keys(&[RlayKeyboardKey::KEY_SPACE]));
+        assert!(state.is_clicked("a"));
+
+        let not_focusable = node("a", ContainerConfig::default(), vec![]);
+        let without_a = layout(root(vec![not_focusable, indexed("b", 2)]));
+        frame(&mut state, &without_a, keys(&[]));
+        assert!(!state.is_focused("a"));
+        assert_eq!(events(&state), [(EventKind::Blur, "a")]);
+        // Enter activates nothing without a focused element
+        frame(&mut state, &without_a, keys(&[RlayKeyboardKey::KEY_ENTER]));
+        assert!(!state.is_clicked("a") && !state.is_clicked("b"));
+    }
+
+    #[test]
+    fn move_the_focus_to_the_nearest_element_in_the_direction_of_the_arrow() {
+        use RlayKeyboardKey::{KEY_DOWN, KEY_LEFT, KEY_RIGHT, KEY_UP};
+
+        let row = |id, children| {
+            let config = ContainerConfig {
+                child_gap: 10,
+                ..Default::default()
+            };
+            node(id, config, children)
+        };
+        // a b c
+        // d   e, e under c and far from d
+        let spacer = node(
+            "spacer",
+            ContainerConfig {
+                sizing: Sizing::new(SizingAxis::Fixed(10.0), SizingAxis::Fixed(10.0)),
+                ..Default::default()
+            },
+            vec![],
+        );
+        let root = ContainerConfig {
+            layout_direction: LayoutDirection::TopToBottom,
+            child_gap: 10,
+            ..Default::default()
+        };
+        let layout = layout(node(
+            "root",
+            root,
+            vec![
+                row("top", vec![button("a"), button("b"), button("c")]),
+                row("bottom", vec![button("d"), spacer, button("e")]),
+            ],
+        ));
+        let mut state = AppState::new();
+        state.set_navigation_keys(Some(NavigationKeys::default()));
+        state.set_focused(Some("b".to_string()));
+        let mut press = |key| {
+            frame(&mut state, &layout, keys(&[key]));
+            state.focused.clone().unwrap()
+        };
+
+        // Straight down there is nothing, and of d and e, as far to the side,
+        // the first one in the tree wins
+        assert_eq!(press(KEY_DOWN), "d");
+        assert_eq!(press(KEY_RIGHT), "e");
+        // On the same column, c is nearer than b
+        assert_eq!(press(KEY_UP), "c");
+        assert_eq!(press(KEY_LEFT), "b");
+        assert_eq!(press(KEY_LEFT), "a");
+        // Outside of a group, the focus stops at the edges
+        assert_eq!(press(KEY_LEFT), "a");
+        assert_eq!(press(KEY_UP), "a");
+    }
+
+    #[test]
+    fn rebuild_the_tree_in_the_storage_of_the_last_frame() {
+        let empty = |id| node(id, ContainerConfig::default(), vec![]);
+        let root = |children| node("root", ContainerConfig::default(), children);
+        let a = |children| node("a", ContainerConfig::default(), children);
+        let mut ctx = ctx();
+
+        let tree = root(vec![a(vec![empty("x"), empty("y")]), empty("b")]);
+        let layout = layout_in(&mut ctx, tree);
+        assert_eq!(ids(&layout), ["root", "a", "x", "y", "b"]);
+        assert!(matches!(
+            ctx.get_element_with_id("a"),
+            Err(RlayError::ElementLaidOut)
+        ));
+
+        // The node of `a` is reused by `b`, without the children of `a`
+        let layout = layout_in(&mut ctx, root(vec![empty("b")]));
+        assert_eq!(ids(&layout), ["root", "b"]);
+
+        let layout = layout_in(&mut ctx, root(vec![a(vec![empty("x")]), empty("b")]));
+        assert_eq!(ids(&layout), ["root", "a", "x", "b"]);
+    }
+
+    #[test]
+    fn derive_the_same_ids_from_the_parent_and_the_position_or_key() {
+        let mut ctx = ctx();
+        // The ids of the children of the root, opened with their keys
+        let mut frame = |keys: &[Option<&str>]| {
+            ctx.clear();
+            ctx.open_element(Element::container(Default::default(), "root".to_owned()));
+            let ids: Vec<_> = keys
+                .iter()
+                .map(|key| {
+                    let id = ctx.next_element_id(None, None, *key);
+                    ctx.open_element(Element::container(Default::default(), id.clone()));
+                    ctx.close_element();
+                    id
+                })
+                .collect();
+            ctx.close_element();
+            ids
+        };
+
+        let first = frame(&[None, None, Some("item")]);
+        assert_eq!(frame(&[None, None, Some("item")]), first);
+        assert_ne!(first[0], first[1]);
+        // A keyed element keeps its id wherever it is among its siblings
+        let moved = frame(&[Some("item"), None]);
+        assert_eq!(moved[0], first[2]);
+        // The others get the id of their position
+        assert_eq!(moved[1], first[1]);
+
+        assert_eq!(
+            ctx.next_element_id(Some("list"), Some("2"), None),
+            "list[2]"
+        );
+    }
+
+    #[test]
+    fn hit_the_elements_where_their_transform_draws_them() {
+        let card = ContainerConfig {
+            sizing: Sizing::new(SizingAxis::Fixed(20.0), SizingAxis::Fixed(20.0)),
+            // Drawn from (40, -10) to (80, 30), and its child in the top
+            // left quarter of it
+            transform: Some(Transform {
+                translation: Point2D::new(50.0, 0.0),
+                scale: Point2D::scalar(2.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let root = ContainerConfig {
+            sizing: Sizing::new(SizingAxis::Fixed(100.0), SizingAxis::Fixed(100.0)),
+            ..Default::default()
+        };
+        let layout = layout(node(
+            "root",
+            root,
+            vec![node("card", card, vec![button("dot")])],
+        ));
+        let mut state = AppState::new();
+        let mut over = |x, y| {
+            frame(
+                &mut state,
+                &layout,
+                pointer(x, y, MouseButtonState::Up, 0.0),
+            );
+            state.pointer_over_ids().to_vec()
+        };
+
+        assert_eq!(over(45.0, 5.0), ["dot", "card", "root"]);
+        assert_eq!(over(75.0, 25.0), ["card", "root"]);
+        // Where they are laid out, they are not drawn anymore
+        assert_eq!(over(5.0, 5.0), ["root"]);
+    }
+}
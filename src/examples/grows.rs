@@ -9,6 +9,8 @@ pub fn grows_example(mut app_ctx: AppCtx) -> Result<AppCtx, RlayError> {
     let x = sizing!(50%, Grow);
 
     let ctx = &mut app_ctx;
+    ctx.register_font_file("Roboto", "src/examples/assets/Roboto-VariableFont.ttf");
+
     rlay!(ctx, view(
             background_color = BLUE,
             padding = [32, 32, 32, 32],
@@ -47,7 +49,7 @@ Labore reprehenderit eos vel. Est ipsum labore ullam eaque sed. Nam repudiandae
 Est labore quis in. Ut quo corporis libero quo ex quis. Expedita totam in velit sequi unde quo.
 ",
                     color = WHITE,
-                    font_name = "Futura",
+                    font_name = "Roboto",
                     text_alignment = TextAlignment::Center
                 ))
             });